@@ -0,0 +1,266 @@
+// cost of a tick of steering at different crowd sizes
+//
+// `cargo bench` runs everything, `cargo bench -- schedule/seek` just one group.
+// The neighbour queries check every pair of ships, so the largest sizes are slow
+use std::hint::black_box;
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use boids::game_plugin::{ShipAssets, ship_bundle};
+use boids::headless_plugin::HeadlessPlugin;
+use boids::input_plugin::{InputPlugin, MousePos};
+use boids::steering_plugin::behaviours::{
+    Arrive, Evade, Flee, FollowPath, Pursue, Seek, Space, Steer, VehicleSnapshot, Wander, par_map,
+};
+use boids::steering_plugin::flock::{Align, Cohere, Neighbours, Separate};
+use boids::steering_plugin::lod::{LodFocus, SteeringLod};
+use boids::steering_plugin::{Behaviour, SteeringPlugin};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const AGENTS: [usize; 4] = [100, 1_000, 10_000, 50_000];
+
+// same as the world bounds
+const HALF_SIZE: Vec2 = Vec2::new(400., 300.);
+
+const PATH: [Vec2; 4] = [
+    Vec2::new(-161., -160.),
+    Vec2::new(162., -160.),
+    Vec2::new(163., 160.),
+    Vec2::new(-164., 160.),
+];
+
+fn random_vehicles(agents: usize) -> Vec<VehicleSnapshot> {
+    let mut rng = StdRng::seed_from_u64(1);
+
+    (0..agents)
+        .map(|_| VehicleSnapshot {
+            position: Vec2::new(
+                rng.random_range(-HALF_SIZE.x..HALF_SIZE.x),
+                rng.random_range(-HALF_SIZE.y..HALF_SIZE.y),
+            ),
+            velocity: Vec2::new(
+                rng.random_range(-250. ..250.),
+                rng.random_range(-250. ..250.),
+            ),
+            max_speed: 250.,
+            max_force: 400.,
+            space: Space::Torus {
+                half_size: HALF_SIZE,
+            },
+        })
+        .collect()
+}
+
+fn steer_all(vehicles: &[VehicleSnapshot], behaviour: &dyn Steer) -> Vec2 {
+    vehicles
+        .iter()
+        .map(|vehicle| behaviour.steer(black_box(vehicle)))
+        .sum()
+}
+
+/// Each behaviour's maths for every ship, without Bevy
+fn behaviours(c: &mut Criterion) {
+    let mut group = c.benchmark_group("behaviours");
+
+    for agents in AGENTS {
+        let vehicles = random_vehicles(agents);
+        let target = Vec2::new(100., 50.);
+        let target_velocity = Vec2::new(-80., 40.);
+        group.throughput(Throughput::Elements(agents as u64));
+
+        let behaviours: [(&str, Box<dyn Steer>); 7] = [
+            ("seek", Box::new(Seek { target })),
+            ("flee", Box::new(Flee { threat: target })),
+            (
+                "arrive",
+                Box::new(Arrive {
+                    target,
+                    delta: 1. / 64.,
+                    ..default()
+                }),
+            ),
+            (
+                "wander",
+                Box::new(Wander {
+                    distance: 100.,
+                    radius: 50.,
+                    theta: 1.,
+                }),
+            ),
+            (
+                "pursue",
+                Box::new(Pursue {
+                    target_position: target,
+                    target_velocity,
+                }),
+            ),
+            (
+                "evade",
+                Box::new(Evade {
+                    threat_position: target,
+                    threat_velocity: target_velocity,
+                }),
+            ),
+            (
+                "path",
+                Box::new(FollowPath {
+                    points: &PATH,
+                    radius: 20.,
+                    look_ahead: 15.,
+                }),
+            ),
+        ];
+
+        for (name, behaviour) in &behaviours {
+            group.bench_with_input(BenchmarkId::new(*name, agents), &vehicles, |b, vehicles| {
+                b.iter(|| steer_all(vehicles, behaviour.as_ref()));
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Flocking for every ship, with every other ship as a possible neighbour
+fn neighbours(c: &mut Criterion) {
+    let mut group = c.benchmark_group("neighbours");
+    group.sample_size(10);
+
+    for agents in AGENTS {
+        let vehicles = random_vehicles(agents);
+        let flock: Neighbours = vehicles
+            .iter()
+            .map(|vehicle| (vehicle.position, vehicle.velocity))
+            .collect();
+        group.throughput(Throughput::Elements(agents as u64));
+
+        let separate = Separate {
+            neighbours: &flock,
+            distance: 25.,
+        };
+        let align = Align {
+            neighbours: &flock,
+            radius: 60.,
+        };
+        let cohere = Cohere {
+            neighbours: &flock,
+            radius: 60.,
+        };
+
+        group.bench_function(BenchmarkId::new("separate", agents), |b| {
+            b.iter(|| steer_all(&vehicles, &separate));
+        });
+        group.bench_function(BenchmarkId::new("align", agents), |b| {
+            b.iter(|| steer_all(&vehicles, &align));
+        });
+        group.bench_function(BenchmarkId::new("cohere", agents), |b| {
+            b.iter(|| steer_all(&vehicles, &cohere));
+        });
+
+        // all three together, as the prey in the ecosystem flock
+        let flock = |vehicle: &VehicleSnapshot| {
+            separate.steer(vehicle) * 1.5 + align.steer(vehicle) + cohere.steer(vehicle)
+        };
+        group.bench_function(BenchmarkId::new("flock", agents), |b| {
+            b.iter(|| vehicles.iter().map(flock).collect::<Vec<_>>());
+        });
+        group.bench_function(BenchmarkId::new("flock_parallel", agents), |b| {
+            b.iter(|| par_map(&vehicles, flock));
+        });
+    }
+    group.finish();
+}
+
+/// Headless app in `behaviour` with `agents` ships, each update one fixed tick
+fn app(behaviour: Behaviour, agents: usize) -> App {
+    let mut app = App::new();
+    app.add_plugins((HeadlessPlugin, InputPlugin, SteeringPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1. / 64.,
+        )))
+        .insert_resource(MousePos(Vec2::new(100., 50.)));
+    app.finish();
+    app.cleanup();
+
+    let assets = ShipAssets::placeholder();
+    for vehicle in random_vehicles(agents) {
+        app.world_mut().spawn((
+            ship_bundle(&assets, vehicle.position),
+            LinearVelocity(vehicle.velocity),
+        ));
+    }
+
+    app.world_mut()
+        .resource_mut::<NextState<Behaviour>>()
+        .set(behaviour);
+    // settle into the behaviour, and past the first update's setup
+    for _ in 0..3 {
+        app.update();
+    }
+    app
+}
+
+/// A whole `App::update` - steering, physics and everything else that runs
+/// each tick. Evolve and neuro-evolve are left out as they spawn their own ships
+fn schedule(c: &mut Criterion) {
+    let behaviours = [
+        ("seek", Behaviour::Seek),
+        ("arrive", Behaviour::Arrive),
+        ("wander", Behaviour::Wander),
+        ("pursue", Behaviour::Pursue),
+        ("flee", Behaviour::Flee),
+        ("evade", Behaviour::Evade),
+        ("path", Behaviour::PathFollow),
+        ("queue", Behaviour::Queue),
+        ("ecosystem", Behaviour::Ecosystem),
+        ("brain", Behaviour::Brain),
+    ];
+
+    for (name, behaviour) in behaviours {
+        let mut group = c.benchmark_group(format!("schedule/{name}"));
+        group.sample_size(10);
+
+        for agents in AGENTS {
+            group.throughput(Throughput::Elements(agents as u64));
+            group.bench_function(BenchmarkId::from_parameter(agents), |b| {
+                // only built when this benchmark isn't filtered out
+                let mut app = app(behaviour.clone(), agents);
+                b.iter(|| app.update());
+            });
+        }
+        group.finish();
+    }
+}
+
+/// The same as `schedule`, with level of detail focused on one corner
+fn lod(c: &mut Criterion) {
+    let behaviours = [
+        ("seek", Behaviour::Seek),
+        ("ecosystem", Behaviour::Ecosystem),
+    ];
+
+    for (name, behaviour) in behaviours {
+        let mut group = c.benchmark_group(format!("lod/{name}"));
+        group.sample_size(10);
+
+        for agents in AGENTS {
+            group.throughput(Throughput::Elements(agents as u64));
+            group.bench_function(BenchmarkId::from_parameter(agents), |b| {
+                let mut app = app(behaviour.clone(), agents);
+                app.insert_resource(SteeringLod {
+                    enabled: true,
+                    focus: LodFocus::Point(-HALF_SIZE),
+                    ..default()
+                });
+                b.iter(|| app.update());
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, behaviours, neighbours, schedule, lod);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+//! Adds an "Orbit" behaviour from outside the crate. It appears after the
+//! built in behaviours, with its own button, shortcut and palette entry
+//!
+//! `cargo run --example custom_behaviour`, with any of the options `cargo run`
+//! takes, e.g. `-- --headless --ticks 600`
+use bevy::color::palettes::css::GOLD;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+
+use boids::AppPlugin;
+use boids::cli::Cli;
+use boids::game_plugin::GamePlugin;
+use boids::headless_plugin::HeadlessPlugin;
+use boids::input_plugin::InputPlugin;
+use boids::steering_plugin::SteeringPlugin;
+use boids::steering_plugin::custom::{ShipContext, SteeringBehaviour, SteeringBehaviourAppExt};
+use boids::steering_plugin::debug::DebugCategory;
+
+/// Circle the mouse anticlockwise
+#[derive(Resource)]
+struct Orbit {
+    radius: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self { radius: 120. }
+    }
+}
+
+impl SteeringBehaviour for Orbit {
+    const NAME: &'static str = "Orbit";
+    const DESCRIPTION: &'static str = "Circle the mouse";
+    // the orbit is drawn with the path, on F6
+    const DEBUG_CATEGORY: DebugCategory = DebugCategory::Path;
+
+    fn steer(&mut self, ship: &ShipContext) -> Vec2 {
+        let to_centre = ship.mouse - ship.vehicle.position;
+        let distance = to_centre.length();
+        let inwards = to_centre.normalize_or_zero();
+
+        // go round, while correcting towards the orbit
+        let around = inwards.perp() * ship.vehicle.max_speed;
+        let correction = inwards * (distance - self.radius);
+        let desired = (around + correction).clamp_length_max(ship.vehicle.max_speed);
+
+        desired - ship.vehicle.velocity
+    }
+
+    fn setup(&mut self, _commands: &mut Commands) {
+        info!("Orbiting at {}", self.radius);
+    }
+
+    fn draw_debug(&self, ship: &ShipContext, gizmos: &mut Gizmos) {
+        gizmos.circle_2d(ship.mouse, self.radius, GOLD);
+    }
+}
+
+// the same as the crate's own main, with the behaviour registered
+fn main() {
+    let cli = match Cli::load() {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut app = App::new();
+    if cli.headless {
+        app.add_plugins((
+            HeadlessPlugin,
+            LogPlugin {
+                level: cli.log_level,
+                ..default()
+            },
+            InputPlugin,
+            SteeringPlugin,
+        ));
+    } else {
+        app.add_plugins(AppPlugin {
+            game: GamePlugin {
+                window_size: cli.window_size(),
+                log_level: cli.log_level,
+            },
+        });
+    }
+
+    app.register_steering_behaviour::<Orbit>();
+    // after the other plugins, as it changes what they've set up
+    app.add_plugins(cli).run();
+}
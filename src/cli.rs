@@ -0,0 +1,334 @@
+use bevy::log::Level;
+use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::game_plugin::{ShipAssets, random_position, ship_bundle};
+use crate::steering_plugin::arrive::ArriveSettings;
+use crate::steering_plugin::behaviours::Deceleration;
+use crate::steering_plugin::evolution::GenerationLimit;
+use crate::steering_plugin::lod::SteeringLod;
+use crate::steering_plugin::svg::SvgExport;
+use crate::steering_plugin::trails::{HeatmapExport, ShowTrails};
+use crate::steering_plugin::{Behaviour, OutputDir, WorldBounds};
+
+/// Steering behaviours, with or without a window
+///
+/// e.g. `cargo run -- --behaviour evolve --headless --generations 5 --heatmap`
+#[derive(Parser, Clone, Debug)]
+#[command(version)]
+pub struct Cli {
+    /// Behaviour to start in
+    #[arg(short, long, value_enum)]
+    pub behaviour: Option<Behaviour>,
+
+    /// Ships to start with, at random positions
+    #[arg(short, long)]
+    pub agents: Option<usize>,
+
+    /// Seed for the random number generator, so runs can be repeated
+    #[arg(short, long)]
+    pub seed: Option<u64>,
+
+    /// RON file with a starting setup, see `scenarios/`. Other options override it
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 1280.)]
+    pub width: f32,
+
+    #[arg(long, default_value_t = 720.)]
+    pub height: f32,
+
+    /// Run without a window, as fast as possible
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Fixed ticks (64 a second) to run before exiting
+    #[arg(short, long)]
+    pub ticks: Option<u32>,
+
+    /// Generations to run in evolve and neuro-evolve before exiting.
+    /// Defaults to 20 when headless without `--ticks`
+    #[arg(short, long)]
+    pub generations: Option<u32>,
+
+    /// Where heatmaps and scenes are saved
+    #[arg(short, long, default_value = ".")]
+    pub output_dir: PathBuf,
+
+    /// Save heatmap.png to the output directory on exit
+    #[arg(long)]
+    pub heatmap: bool,
+
+    /// Save scene.svg, with recent trajectories, to the output directory on exit
+    #[arg(long)]
+    pub svg: bool,
+
+    /// Only steer distant and off screen ships every few ticks, for big scenes
+    #[arg(long)]
+    pub lod: bool,
+
+    /// How ships slow down as they arrive
+    #[arg(long, value_enum)]
+    pub deceleration: Option<Deceleration>,
+
+    /// Distance from the target arrive starts slowing down at, for linear and
+    /// quadratic deceleration
+    #[arg(long)]
+    pub slowing_radius: Option<f32>,
+
+    /// Distance from the target arrive counts as there
+    #[arg(long)]
+    pub arrive_tolerance: Option<f32>,
+
+    /// Speed below which a ship at its arrive target has settled
+    #[arg(long)]
+    pub settle_speed: Option<f32>,
+
+    /// error, warn, info, debug or trace
+    #[arg(long, default_value_t = Level::INFO)]
+    pub log_level: Level,
+
+    // ships at fixed positions, only set by scenarios
+    #[arg(skip)]
+    pub ships: Vec<Vec2>,
+}
+
+/// Starting setup loaded from a RON file. Anything missing is left to the
+/// command line options or their defaults
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Scenario {
+    /// As given to `--behaviour`
+    pub behaviour: Option<String>,
+    pub agents: Option<usize>,
+    pub seed: Option<u64>,
+    pub ticks: Option<u32>,
+    pub generations: Option<u32>,
+    /// Ships at these positions, as well as any `agents`
+    pub ships: Vec<(f32, f32)>,
+}
+
+impl Scenario {
+    pub fn from_ron(ron: &str) -> Result<Self, String> {
+        ron::from_str(ron).map_err(|e| e.to_string())
+    }
+}
+
+impl Cli {
+    /// Parses the command line, filling in anything not given from the scenario file
+    pub fn load() -> Result<Self, String> {
+        let cli = Cli::parse();
+
+        let Some(path) = cli.scenario.clone() else {
+            return Ok(cli);
+        };
+        let ron = fs::read_to_string(&path)
+            .map_err(|e| format!("Couldn't read scenario {}: {e}", path.display()))?;
+        let scenario = Scenario::from_ron(&ron)
+            .map_err(|e| format!("Couldn't parse scenario {}: {e}", path.display()))?;
+
+        cli.with_scenario(scenario)
+            .map_err(|e| format!("Bad scenario {}: {e}", path.display()))
+    }
+
+    pub fn with_scenario(self, scenario: Scenario) -> Result<Self, String> {
+        let behaviour = scenario
+            .behaviour
+            .map(|name| Behaviour::from_str(&name, true))
+            .transpose()?;
+
+        Ok(Self {
+            behaviour: self.behaviour.or(behaviour),
+            agents: self.agents.or(scenario.agents),
+            seed: self.seed.or(scenario.seed),
+            ticks: self.ticks.or(scenario.ticks),
+            generations: self.generations.or(scenario.generations),
+            ships: scenario
+                .ships
+                .into_iter()
+                .map(|(x, y)| Vec2::new(x, y))
+                .collect(),
+            ..self
+        })
+    }
+
+    pub fn window_size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+
+    /// The arrive options over the defaults, if any were given
+    pub fn arrive_settings(&self) -> Option<ArriveSettings> {
+        let defaults = ArriveSettings::default();
+        let arrive = ArriveSettings {
+            deceleration: self.deceleration.unwrap_or(defaults.deceleration),
+            slowing_radius: self.slowing_radius.unwrap_or(defaults.slowing_radius),
+            tolerance: self.arrive_tolerance.unwrap_or(defaults.tolerance),
+            settle_speed: self.settle_speed.unwrap_or(defaults.settle_speed),
+        };
+
+        (arrive != defaults).then_some(arrive)
+    }
+}
+
+/// Ships spawned at startup
+#[derive(Resource)]
+pub struct StartingShips {
+    pub random: usize,
+    pub positions: Vec<Vec2>,
+}
+
+/// Fixed ticks left before exiting
+#[derive(Resource)]
+pub struct TickLimit(pub u32);
+
+/// Applies the options to an app which already has its plugins
+impl Plugin for Cli {
+    fn build(&self, app: &mut App) {
+        if let Some(behaviour) = &self.behaviour {
+            app.insert_state(behaviour.clone());
+        }
+
+        if let Some(seed) = self.seed {
+            let world = app.world_mut();
+            let global = world
+                .query_filtered::<Entity, With<Global>>()
+                .single(world)
+                .expect("SteeringPlugin should add the global rng");
+            world
+                .entity_mut(global)
+                .insert(RngSeed::<WyRand>::from_seed(seed.to_le_bytes()));
+        }
+
+        let starting_ships = match (self.agents, self.ships.is_empty()) {
+            (None, true) if !self.headless => StartingShips {
+                random: 0,
+                positions: vec![Vec2::new(0., -150.)],
+            },
+            (agents, _) => StartingShips {
+                random: agents.unwrap_or(0),
+                positions: self.ships.clone(),
+            },
+        };
+        app.insert_resource(starting_ships)
+            .add_systems(Startup, spawn_starting_ships_system);
+
+        if let Some(ticks) = self.ticks {
+            app.insert_resource(TickLimit(ticks))
+                .add_systems(FixedLast, tick_limit_system);
+        }
+
+        let evolving = matches!(
+            self.behaviour,
+            Some(Behaviour::Evolve | Behaviour::NeuroEvolve)
+        );
+        let generations = match self.generations {
+            // would otherwise run forever
+            None if self.headless && evolving && self.ticks.is_none() => Some(20),
+            generations => generations,
+        };
+        if let Some(generations) = generations {
+            app.insert_resource(GenerationLimit(generations));
+        }
+
+        if let Err(e) = fs::create_dir_all(&self.output_dir) {
+            warn!(
+                "Couldn't create output directory {}: {e}",
+                self.output_dir.display()
+            );
+        }
+        app.insert_resource(OutputDir(self.output_dir.clone()));
+        if self.heatmap {
+            app.insert_resource(HeatmapExport(self.output_dir.join("heatmap.png")));
+        }
+        if self.lod {
+            app.insert_resource(SteeringLod {
+                enabled: true,
+                ..default()
+            });
+        }
+        if let Some(arrive) = self.arrive_settings() {
+            app.insert_resource(arrive);
+        }
+        if self.svg {
+            // trajectories are only recorded while trails are shown
+            app.insert_resource(SvgExport(self.output_dir.join("scene.svg")))
+                .insert_resource(ShowTrails(true));
+        }
+    }
+}
+
+fn spawn_starting_ships_system(
+    mut commands: Commands,
+    starting_ships: Res<StartingShips>,
+    ship_assets: Res<ShipAssets>,
+    bounds: Res<WorldBounds>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    for position in &starting_ships.positions {
+        commands.spawn(ship_bundle(&ship_assets, *position));
+    }
+
+    for _ in 0..starting_ships.random {
+        let position = random_position(&mut *rng, &bounds);
+        commands.spawn(ship_bundle(&ship_assets, position));
+    }
+}
+
+fn tick_limit_system(mut limit: ResMut<TickLimit>, mut exit: EventWriter<AppExit>) {
+    limit.0 = limit.0.saturating_sub(1);
+    if limit.0 == 0 {
+        exit.write(AppExit::Success);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::{Cli, Scenario};
+    use crate::steering_plugin::Behaviour;
+    use crate::steering_plugin::arrive::ArriveSettings;
+    use bevy::prelude::*;
+    use clap::Parser;
+
+    #[test]
+    fn cli_should_override_scenario() {
+        let scenario = Scenario::from_ron(
+            "#![enable(implicit_some)]
+            (behaviour: \"queue\", agents: 30, seed: 7, ships: [(10., -20.)])",
+        )
+        .unwrap();
+        let cli = Cli::try_parse_from(["boids", "--agents", "5", "--behaviour", "path-follow"])
+            .unwrap()
+            .with_scenario(scenario)
+            .unwrap();
+
+        assert_eq!(cli.behaviour, Some(Behaviour::PathFollow));
+        assert_eq!(cli.agents, Some(5));
+        assert_eq!(cli.seed, Some(7));
+        assert_eq!(cli.ticks, None);
+        assert_eq!(cli.ships, vec![Vec2::new(10., -20.)]);
+    }
+
+    #[test]
+    fn arrive_options_should_override_defaults() {
+        let cli = Cli::try_parse_from(["boids", "--slowing-radius", "150", "--settle-speed", "2"])
+            .unwrap();
+
+        assert_eq!(
+            cli.arrive_settings(),
+            Some(ArriveSettings {
+                slowing_radius: 150.,
+                settle_speed: 2.,
+                ..default()
+            })
+        );
+        assert_eq!(
+            Cli::try_parse_from(["boids"]).unwrap().arrive_settings(),
+            None
+        );
+    }
+}
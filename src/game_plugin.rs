@@ -1,18 +1,55 @@
 use avian2d::prelude::*;
 use bevy::color::palettes::css::{BLUE, RED};
+use bevy::log::{Level, LogPlugin};
 use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+
+use crate::input_plugin::{MainCamera, MousePos, Target};
+use crate::steering_plugin::forces::{LastSteering, MaxSteeringForce, Steering};
+use crate::steering_plugin::lod::LodState;
+use crate::steering_plugin::orca::Avoidance;
+use crate::steering_plugin::trails::Trail;
+use crate::steering_plugin::{Behaviour, Ship, WanderTheta, WorldBounds, WrapEdges};
+
+/// The windowed app. Window and logging options have to be set as
+/// `DefaultPlugins` are added, so they're passed in here
+#[derive(Clone)]
+pub struct GamePlugin {
+    pub window_size: Vec2,
+    pub log_level: Level,
+}
 
-use crate::input_plugin::{MainCamera, Target};
-use crate::steering_plugin::{Behaviour, Ship, WrapEdges};
-
-pub struct GamePlugin;
+impl Default for GamePlugin {
+    fn default() -> Self {
+        Self {
+            window_size: Vec2::new(1280., 720.),
+            log_level: Level::INFO,
+        }
+    }
+}
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((DefaultPlugins, PhysicsPlugins::default()))
+        let window = Window {
+            resolution: self.window_size.into(),
+            ..default()
+        };
+        let default_plugins = DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(window),
+                ..default()
+            })
+            .set(LogPlugin {
+                level: self.log_level,
+                ..default()
+            });
+
+        app.add_plugins((default_plugins, PhysicsPlugins::default()))
             .init_state::<Behaviour>()
+            .init_resource::<ShipAssets>()
             .add_systems(Startup, setup)
-            .add_systems(Update, (clamp_edges_system, button_handler_system));
+            .add_systems(Update, ship_controls_system);
     }
 }
 
@@ -27,203 +64,138 @@ fn setup(
     let circle = Circle::new(target_size);
     let red: Color = RED.into();
 
-    let ship_height = 15.0;
-    let ship_width = 10.0;
-    let blue: Color = BLUE.into();
-    let triangle = Triangle2d::new(
-        Vec2::Y * ship_height,
-        Vec2::new(-ship_width, -ship_width),
-        Vec2::new(ship_width, -ship_width),
-    );
-
     commands.spawn((
         Mesh2d(meshes.add(circle)),
         MeshMaterial2d(materials.add(ColorMaterial::from(red))),
         Transform::from_xyz(-150., 0., 0.),
         Target,
     ));
-    commands.spawn((
-        Mesh2d(meshes.add(triangle)),
-        MeshMaterial2d(materials.add(ColorMaterial::from(blue))),
-        Transform::from_xyz(0., -150., 0.),
-        RigidBody::Kinematic,
-        MaxLinearSpeed(250.0),
-        MaxAngularSpeed(10.0),
-        Collider::circle(9.),
-        Ship,
-        WrapEdges,
-    ));
 
     commands.spawn((
+        Text::new(
+            "Right click: add ship | N: add 10 ships | C: clear ships | O: avoidance | F: forces | V: vehicle\n\
+             Space: pause | .: step | [ ]: slower / faster | D: debug | 1-9 0 - =: behaviour | /: find behaviour\n\
+             Left click: inspect ship | Esc: stop inspecting | T: trails | H: heatmap | E: save heatmap | S: save SVG",
+        ),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
         Node {
-            width: Val::Percent(100.0),
-            height: Val::Percent(100.0),
-            align_items: AlignItems::Start,
-            justify_content: JustifyContent::Start,
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
             ..default()
         },
-        children![(
-            Node {
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::Center,
-                ..default()
-            },
-            children![
-                // not automatic, make sure this matches #[default] in Behaviour enum
-                create_selected_button("Seek"),
-                create_normal_button("Arrive"),
-                create_normal_button("Wander"),
-                create_normal_button("Pursue"),
-                create_normal_button("Flee"),
-                create_normal_button("Evade"),
-                create_normal_button("Path"),
-            ],
-        )],
     ));
 }
 
-#[derive(Component)]
-struct SelectedOption;
-
-const NORMAL_BUTTON: Color = Color::srgb(0.95, 0.75, 0.35);
-const HOVERED_BUTTON: Color = Color::srgb(1., 0.83, 0.52);
-const HOVERED_SELECTED_BUTTON: Color = Color::srgb(1., 0.54, 0.8);
-const SELECTED_BUTTON: Color = Color::srgb(0.96, 0.05, 0.7);
-
-fn button(btn_text: &str) -> impl Bundle + use<> {
-    let behaviour = match btn_text {
-        "Arrive" => Behaviour::Arrive,
-        "Wander" => Behaviour::Wander,
-        "Pursue" => Behaviour::Pursue,
-        "Flee" => Behaviour::Flee,
-        "Evade" => Behaviour::Evade,
-        "Path" => Behaviour::PathFollow,
-        _ => Behaviour::Seek,
-    };
+/// Ship triangle, pointing up
+pub const SHIP_SHAPE: [Vec2; 3] = [
+    Vec2::new(0., 15.),
+    Vec2::new(-10., -10.),
+    Vec2::new(10., -10.),
+];
+
+/// Top speed of a new ship, before any behaviour changes it
+pub const SHIP_MAX_SPEED: f32 = 250.;
+
+/// Shared mesh and material so every ship doesn't need its own
+#[derive(Resource)]
+pub struct ShipAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<ColorMaterial>,
+}
 
-    (
-        Button,
-        Node {
-            width: Val::Px(100.0),
-            height: Val::Px(35.0),
-            border: UiRect::all(Val::Px(3.0)),
-            // horizontally center child text
-            justify_content: JustifyContent::Center,
-            // vertically center child text
-            align_items: AlignItems::Center,
-            margin: UiRect {
-                top: Val::Px(15.),
-                ..default()
-            },
-            ..default()
-        },
-        behaviour,
-        BorderRadius::MAX,
-        children![(
-            Text::new(btn_text),
-            TextFont {
-                font_size: 16.0,
-                ..default()
-            },
-            TextColor(Color::srgb(0.24, 0.21, 0.19)),
-            TextShadow {
-                color: Color::srgb(0.64, 0.61, 0.59),
-                offset: Vec2 { x: 0.5, y: 0.5 }
-            },
-        )],
-    )
+impl ShipAssets {
+    /// Empty handles, for headless apps, tests and benchmarks which never draw
+    /// the ships
+    pub fn placeholder() -> Self {
+        Self {
+            mesh: Handle::default(),
+            material: Handle::default(),
+        }
+    }
+}
+
+impl FromWorld for ShipAssets {
+    fn from_world(world: &mut World) -> Self {
+        let blue: Color = BLUE.into();
+        let triangle = Triangle2d::new(SHIP_SHAPE[0], SHIP_SHAPE[1], SHIP_SHAPE[2]);
+
+        Self {
+            mesh: world.resource_mut::<Assets<Mesh>>().add(triangle),
+            material: world
+                .resource_mut::<Assets<ColorMaterial>>()
+                .add(ColorMaterial::from(blue)),
+        }
+    }
 }
-fn create_normal_button(btn_text: &str) -> impl Bundle + use<> {
+
+pub fn ship_bundle(assets: &ShipAssets, position: Vec2) -> impl Bundle + use<> {
     (
-        button(btn_text),
-        BackgroundColor(NORMAL_BUTTON),
-        BorderColor(Color::BLACK),
+        Mesh2d(assets.mesh.clone()),
+        MeshMaterial2d(assets.material.clone()),
+        Transform::from_xyz(position.x, position.y, 0.),
+        RigidBody::Kinematic,
+        MaxLinearSpeed(SHIP_MAX_SPEED),
+        MaxAngularSpeed(10.0),
+        Collider::circle(9.),
+        (
+            Mass(1.),
+            MaxSteeringForce(400.),
+            // only used when ships are dynamic
+            ExternalForce::default(),
+            LockedAxes::ROTATION_LOCKED,
+        ),
+        (
+            Steering::default(),
+            LastSteering::default(),
+            LodState::default(),
+            Trail::default(),
+        ),
+        Avoidance::default(),
+        // straight ahead
+        WanderTheta(0.),
+        Ship,
+        WrapEdges,
     )
 }
 
-fn create_selected_button(btn_text: &str) -> impl Bundle + use<> {
-    (
-        button(btn_text),
-        BackgroundColor(SELECTED_BUTTON),
-        SelectedOption,
-        BorderColor(Color::WHITE),
+/// Anywhere inside `bounds`
+pub fn random_position(rng: &mut impl Rng, bounds: &WorldBounds) -> Vec2 {
+    Vec2::new(
+        rng.random_range(-bounds.half_size.x..bounds.half_size.x),
+        rng.random_range(-bounds.half_size.y..bounds.half_size.y),
     )
 }
 
-// TODO highlight current behaviour
-#[allow(clippy::type_complexity)]
-fn button_handler_system(
+#[allow(clippy::too_many_arguments)]
+fn ship_controls_system(
     mut commands: Commands,
-    mut next_state: ResMut<NextState<Behaviour>>,
-    mut interaction_query: Query<
-        (
-            Entity,
-            &Interaction,
-            &mut BackgroundColor,
-            &mut BorderColor,
-            &Behaviour,
-        ),
-        (Changed<Interaction>, With<Button>, Without<SelectedOption>),
-    >,
-    current_selected: Single<
-        (Entity, &mut BackgroundColor, &mut BorderColor),
-        With<SelectedOption>,
-    >,
-    mode: Res<State<Behaviour>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse_pos: Res<MousePos>,
+    ship_assets: Res<ShipAssets>,
+    bounds: Res<WorldBounds>,
+    ships: Query<Entity, With<Ship>>,
+    mut rng: GlobalEntropy<WyRand>,
 ) {
-    let (previous_button, mut previous_button_color, mut previous_button_border) =
-        current_selected.into_inner();
-
-    for (entity, interaction, mut bg, mut border, behaviour) in &mut interaction_query {
-        if *interaction == Interaction::Pressed && *behaviour != **mode {
-            *previous_button_color = NORMAL_BUTTON.into();
-            *previous_button_border = Color::BLACK.into();
-            commands.entity(previous_button).remove::<SelectedOption>();
-            commands.entity(entity).insert(SelectedOption);
-            next_state.set(behaviour.clone());
-            *bg = SELECTED_BUTTON.into();
-        }
-
-        let is_selected = *behaviour == **mode;
-        match (interaction, is_selected) {
-            (Interaction::Hovered, true) => {
-                border.0 = Color::WHITE;
-                *bg = HOVERED_SELECTED_BUTTON.into();
-            }
-            (Interaction::Hovered, false) => {
-                border.0 = Color::WHITE;
-                *bg = HOVERED_BUTTON.into();
-            }
-            (Interaction::None, true) => {
-                *bg = SELECTED_BUTTON.into();
-                border.0 = Color::BLACK;
-            }
-            (Interaction::None, false) => {
-                *bg = NORMAL_BUTTON.into();
-                border.0 = Color::BLACK;
-            }
-            _ => {}
-        }
+    if mouse_buttons.just_pressed(MouseButton::Right) {
+        commands.spawn(ship_bundle(&ship_assets, mouse_pos.0));
     }
-}
 
-// keep in middle of screen
-fn clamp_edges_system(mut query: Query<&mut Position, With<WrapEdges>>) {
-    let half_max_width = 400.;
-    let half_max_height = 300.;
-
-    // bevy screen centre is 0,0
-    for mut position in &mut query {
-        if position.x > half_max_width {
-            position.x = -(half_max_width);
-        } else if position.x < (-half_max_width) {
-            position.x = half_max_width;
+    if keyboard.just_pressed(KeyCode::KeyN) {
+        let count = 10;
+        for _ in 0..count {
+            let position = random_position(&mut *rng, &bounds);
+            commands.spawn(ship_bundle(&ship_assets, position));
         }
+    }
 
-        if position.y > half_max_height {
-            position.y = -(half_max_height);
-        } else if position.y < (-half_max_height) {
-            position.y = half_max_height;
+    if keyboard.just_pressed(KeyCode::KeyC) {
+        for ship in &ships {
+            commands.entity(ship).despawn();
         }
     }
 }
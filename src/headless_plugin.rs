@@ -0,0 +1,39 @@
+use avian2d::prelude::*;
+use bevy::gizmos::GizmoPlugin;
+use bevy::prelude::*;
+use bevy::render::render_resource::Shader;
+use bevy::scene::ScenePlugin;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use std::time::Duration;
+
+use crate::game_plugin::ShipAssets;
+use crate::steering_plugin::Behaviour;
+
+/// Runs the simulation without a window, as fast as possible.
+/// Use instead of `GamePlugin`
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+            StatesPlugin,
+            bevy::input::InputPlugin,
+        ))
+        // normally added by the render plugins
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<Shader>()
+        .add_plugins((GizmoPlugin, PhysicsPlugins::default()))
+        // each update is a fixed step, rather than waiting for real time to pass
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1. / 60.,
+        )))
+        .init_state::<Behaviour>()
+        .init_resource::<ShipAssets>();
+    }
+}
@@ -1,12 +1,33 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use crate::steering_plugin::OutputDir;
+use crate::steering_plugin::debug::{DebugCategory, DebugOverlay};
+use crate::steering_plugin::forces::DynamicShips;
+use crate::steering_plugin::lod::SteeringLod;
+use crate::steering_plugin::orca::LocalAvoidance;
+use crate::steering_plugin::svg::SceneQuery;
+use crate::steering_plugin::trails::{Heatmap, ShowHeatmap, ShowTrails};
+use crate::steering_plugin::vehicle::{Vehicle, VehicleModel};
+
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(MousePos(Vec2::new(0.0, 0.0)))
-            .add_systems(Update, (mouse_cursor_system, move_target_system));
+            .add_systems(
+                Update,
+                (
+                    mouse_cursor_system,
+                    move_target_system,
+                    avoidance_toggle_system,
+                    dynamic_toggle_system,
+                    vehicle_toggle_system,
+                    lod_toggle_system,
+                    debug_toggle_system,
+                    trails_toggle_system,
+                ),
+            );
     }
 }
 
@@ -39,3 +60,85 @@ fn move_target_system(mut query: Query<&mut Transform, With<Target>>, mouse_pos:
         transform.translation = Vec3::new(mouse_pos.0.x, mouse_pos.0.y, 0.);
     }
 }
+
+fn avoidance_toggle_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut avoidance: ResMut<LocalAvoidance>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        avoidance.0 = !avoidance.0;
+    }
+}
+
+fn dynamic_toggle_system(keyboard: Res<ButtonInput<KeyCode>>, mut dynamic: ResMut<DynamicShips>) {
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        dynamic.0 = !dynamic.0;
+    }
+}
+
+fn lod_toggle_system(keyboard: Res<ButtonInput<KeyCode>>, mut lod: ResMut<SteeringLod>) {
+    if keyboard.just_pressed(KeyCode::KeyL) {
+        lod.enabled = !lod.enabled;
+    }
+}
+
+// free movement -> car -> boat
+fn vehicle_toggle_system(keyboard: Res<ButtonInput<KeyCode>>, mut model: ResMut<VehicleModel>) {
+    if keyboard.just_pressed(KeyCode::KeyV) {
+        model.0 = match model.0 {
+            None => Some(Vehicle::Car {
+                turning_radius: 40.,
+            }),
+            Some(Vehicle::Car { .. }) => Some(Vehicle::Boat { max_yaw_rate: 2. }),
+            Some(Vehicle::Boat { .. }) => None,
+        };
+        info!("Vehicle model: {:?}", model.0);
+    }
+}
+
+// D for the whole overlay, F1.. for each category
+fn debug_toggle_system(keyboard: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<DebugOverlay>) {
+    let keys = [
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+    ];
+
+    if keyboard.just_pressed(KeyCode::KeyD) {
+        overlay.enabled = !overlay.enabled;
+    }
+
+    for (key, category) in keys.iter().zip(DebugCategory::ALL) {
+        if keyboard.just_pressed(*key) {
+            overlay.toggle(category);
+        }
+    }
+}
+
+fn trails_toggle_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut trails: ResMut<ShowTrails>,
+    mut show_heatmap: ResMut<ShowHeatmap>,
+    heatmap: Res<Heatmap>,
+    scene: SceneQuery,
+    output: Res<OutputDir>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        trails.0 = !trails.0;
+    }
+    if keyboard.just_pressed(KeyCode::KeyH) {
+        show_heatmap.0 = !show_heatmap.0;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyE) {
+        heatmap.export(&output.0.join("heatmap.png"));
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyS) {
+        scene.scene().export(&output.0.join("scene.svg"));
+    }
+}
@@ -0,0 +1,265 @@
+use avian2d::prelude::*;
+use bevy::color::palettes::css::WHITE;
+use bevy::prelude::*;
+
+use crate::input_plugin::MousePos;
+use crate::math::rotation_to_heading;
+use crate::menu_plugin::NORMAL_BUTTON;
+use crate::steering_plugin::forces::{LastSteering, MaxSteeringForce, SteeringParts};
+use crate::steering_plugin::{Behaviour, Ship, SteeringTargets};
+
+/// Left click a ship to see what it's doing, escape to stop
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup).add_systems(
+            Update,
+            (
+                select_ship_system,
+                inspector_button_system,
+                highlight_system,
+                inspector_text_system,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The ship being inspected
+#[derive(Component)]
+pub struct Selected;
+
+#[derive(Component)]
+struct InspectorPanel;
+
+#[derive(Component)]
+struct InspectorText;
+
+/// Value a panel button changes, and by how much
+#[derive(Component, Clone, Copy)]
+enum InspectorEdit {
+    MaxSpeed(f32),
+    MaxForce(f32),
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(8.0)),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+        InspectorPanel,
+        children![
+            (
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                InspectorText,
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                children![
+                    edit_button("Speed -", InspectorEdit::MaxSpeed(-25.)),
+                    edit_button("Speed +", InspectorEdit::MaxSpeed(25.)),
+                    edit_button("Force -", InspectorEdit::MaxForce(-50.)),
+                    edit_button("Force +", InspectorEdit::MaxForce(50.)),
+                ],
+            ),
+        ],
+    ));
+}
+
+fn edit_button(text: &str, edit: InspectorEdit) -> impl Bundle + use<> {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+            margin: UiRect {
+                top: Val::Px(6.),
+                right: Val::Px(4.),
+                ..default()
+            },
+            ..default()
+        },
+        BackgroundColor(NORMAL_BUTTON),
+        BorderRadius::all(Val::Px(4.)),
+        edit,
+        children![(
+            Text::new(text),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.24, 0.21, 0.19)),
+        )],
+    )
+}
+
+/// Closest ship to `point` within `radius`
+pub fn pick(
+    point: Vec2,
+    radius: f32,
+    ships: impl IntoIterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    ships
+        .into_iter()
+        .filter(|(_, position)| position.distance(point) < radius)
+        .min_by(|(_, a), (_, b)| a.distance(point).total_cmp(&b.distance(point)))
+        .map(|(entity, _)| entity)
+}
+
+fn select_ship_system(
+    mut commands: Commands,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse_pos: Res<MousePos>,
+    ships: Query<(Entity, &Position), With<Ship>>,
+    selected: Query<Entity, With<Selected>>,
+    buttons: Query<&Interaction>,
+) {
+    let clicked = mouse_buttons.just_pressed(MouseButton::Left)
+        // clicks on buttons aren't for the ships underneath
+        && buttons.iter().all(|interaction| *interaction == Interaction::None);
+    if !clicked && !keyboard.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    for entity in &selected {
+        commands
+            .entity(entity)
+            .remove::<(Selected, SteeringParts)>();
+    }
+
+    if clicked {
+        let click_radius = 20.;
+        let picked = pick(
+            mouse_pos.0,
+            click_radius,
+            ships.iter().map(|(entity, position)| (entity, position.0)),
+        );
+
+        if let Some(entity) = picked {
+            // only the inspected ship keeps track of each behaviour's part
+            commands
+                .entity(entity)
+                .insert((Selected, SteeringParts::default()));
+        }
+    }
+}
+
+fn inspector_button_system(
+    buttons: Query<(&Interaction, &InspectorEdit), Changed<Interaction>>,
+    mut selected: Query<(&mut MaxLinearSpeed, &mut MaxSteeringForce), With<Selected>>,
+) {
+    for (interaction, edit) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        for (mut max_speed, mut max_force) in &mut selected {
+            match *edit {
+                InspectorEdit::MaxSpeed(change) => max_speed.0 = (max_speed.0 + change).max(0.),
+                InspectorEdit::MaxForce(change) => max_force.0 = (max_force.0 + change).max(0.),
+            }
+        }
+    }
+}
+
+fn highlight_system(selected: Query<&Position, With<Selected>>, mut gizmos: Gizmos) {
+    for position in &selected {
+        gizmos.circle_2d(position.0, 16., WHITE);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn inspector_text_system(
+    selected: Query<
+        (
+            Entity,
+            &Position,
+            &LinearVelocity,
+            &MaxLinearSpeed,
+            &MaxSteeringForce,
+            &Rotation,
+            &LastSteering,
+            Option<&SteeringParts>,
+        ),
+        With<Selected>,
+    >,
+    mut panel: Single<&mut Node, With<InspectorPanel>>,
+    mut text: Single<&mut Text, With<InspectorText>>,
+    behaviour: Res<State<Behaviour>>,
+    targets: SteeringTargets,
+) {
+    let Ok((entity, position, velocity, max_speed, max_force, rotation, last, parts)) =
+        selected.single()
+    else {
+        panel.display = Display::None;
+        return;
+    };
+    panel.display = Display::Flex;
+
+    let target = targets
+        .of(entity)
+        .map_or("none".to_string(), |target| format!("{target:.0}"));
+
+    let parts = parts.map_or(String::new(), |parts| {
+        parts
+            .0
+            .iter()
+            .map(|(behaviour, steering)| format!("\n  {behaviour}: {steering:.0}"))
+            .collect()
+    });
+
+    text.0 = format!(
+        "Ship {entity}\n\
+         Behaviour: {:?}\n\
+         Position: {:.0}\n\
+         Velocity: {:.0}\n\
+         Speed: {:.0} / {:.0}\n\
+         Heading: {:.0}°\n\
+         Desired velocity: {:.0}\n\
+         Behaviours:{parts}\n\
+         Steering: {:.0} (max force {:.0})\n\
+         Avoidance: {:.0}\n\
+         Target: {target}",
+        behaviour.get(),
+        position.0,
+        velocity.0,
+        velocity.0.length(),
+        max_speed.0,
+        rotation_to_heading(rotation.as_radians()).to_degrees(),
+        last.desired_velocity,
+        last.steering,
+        max_force.0,
+        last.avoidance,
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use crate::inspector_plugin::pick;
+    use bevy::prelude::*;
+
+    #[test]
+    fn pick_should_choose_closest_ship_in_range() {
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+        let ships = [(a, Vec2::new(10., 0.)), (b, Vec2::new(5., 5.))];
+
+        assert_eq!(pick(Vec2::ZERO, 20., ships), Some(b));
+        assert_eq!(pick(Vec2::new(100., 0.), 20., ships), None);
+    }
+}
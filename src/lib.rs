@@ -0,0 +1,37 @@
+pub mod cli;
+pub mod game_plugin;
+pub mod headless_plugin;
+pub mod input_plugin;
+pub mod inspector_plugin;
+pub mod math;
+pub mod menu_plugin;
+pub mod steering_plugin;
+pub mod time_controls_plugin;
+
+use bevy::prelude::*;
+
+use crate::game_plugin::GamePlugin;
+use crate::input_plugin::InputPlugin;
+use crate::inspector_plugin::InspectorPlugin;
+use crate::menu_plugin::MenuPlugin;
+use crate::steering_plugin::SteeringPlugin;
+use crate::time_controls_plugin::TimeControlsPlugin;
+
+/// Everything in the windowed app
+#[derive(Default)]
+pub struct AppPlugin {
+    pub game: GamePlugin,
+}
+
+impl Plugin for AppPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            self.game.clone(),
+            InputPlugin,
+            InspectorPlugin,
+            MenuPlugin,
+            SteeringPlugin,
+            TimeControlsPlugin,
+        ));
+    }
+}
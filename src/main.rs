@@ -1,22 +1,42 @@
-mod game_plugin;
-mod input_plugin;
-mod steering_plugin;
-mod utils;
-
+use bevy::log::LogPlugin;
 use bevy::prelude::*;
 
-use crate::game_plugin::GamePlugin;
-use crate::input_plugin::InputPlugin;
-use crate::steering_plugin::SteeringPlugin;
+use boids::AppPlugin;
+use boids::cli::Cli;
+use boids::game_plugin::GamePlugin;
+use boids::headless_plugin::HeadlessPlugin;
+use boids::input_plugin::InputPlugin;
+use boids::steering_plugin::SteeringPlugin;
 
-pub struct AppPlugin;
+fn main() {
+    let cli = match Cli::load() {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
 
-impl Plugin for AppPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_plugins((GamePlugin, InputPlugin, SteeringPlugin));
+    let mut app = App::new();
+    if cli.headless {
+        app.add_plugins((
+            HeadlessPlugin,
+            LogPlugin {
+                level: cli.log_level,
+                ..default()
+            },
+            InputPlugin,
+            SteeringPlugin,
+        ));
+    } else {
+        app.add_plugins(AppPlugin {
+            game: GamePlugin {
+                window_size: cli.window_size(),
+                log_level: cli.log_level,
+            },
+        });
     }
-}
 
-fn main() {
-    App::new().add_plugins(AppPlugin).run();
+    // after the other plugins, as it changes what they've set up
+    app.add_plugins(cli).run();
 }
@@ -0,0 +1,243 @@
+// vector and angle helpers shared by the steering behaviours
+//
+// Angles are in radians. A heading is the direction of travel, anticlockwise
+// from the positive x axis as in `Vec2::to_angle`. Ship meshes point up, so a
+// ship's `Rotation` is a quarter turn behind its heading
+use bevy::prelude::*;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// Normalise and scale a vector. Zero stays zero
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::set_magnitude;
+///
+/// assert_eq!(set_magnitude(Vec2::new(3., 4.), 10.), Vec2::new(6., 8.));
+/// assert_eq!(set_magnitude(Vec2::ZERO, 10.), Vec2::ZERO);
+/// ```
+pub fn set_magnitude(vector: Vec2, magnitude: f32) -> Vec2 {
+    vector.normalize_or_zero() * magnitude
+}
+
+/// Shorten a vector to at most `max`, keeping its direction
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::limit;
+///
+/// assert_eq!(limit(Vec2::new(30., 40.), 5.), Vec2::new(3., 4.));
+/// assert_eq!(limit(Vec2::new(3., 4.), 10.), Vec2::new(3., 4.));
+/// ```
+pub fn limit(vector: Vec2, max: f32) -> Vec2 {
+    vector.clamp_length_max(max)
+}
+
+/// Linearly map `value` from the range `start1..stop1` to `start2..stop2`.
+/// Values outside the first range map outside the second, so clamp the result
+/// if that matters. Either range can run backwards
+/// ```
+/// use boids::math::map_range;
+///
+/// assert_eq!(map_range(5., 0., 10., 0., 100.), 50.);
+/// assert_eq!(map_range(15., 10., 20., 100., 0.), 50.);
+/// assert_eq!(map_range(20., 0., 10., 0., 100.), 200.);
+/// ```
+pub fn map_range(value: f32, start1: f32, stop1: f32, start2: f32, stop2: f32) -> f32 {
+    if start1 == stop1 {
+        return start2;
+    }
+
+    start2 + (value - start1) / (stop1 - start1) * (stop2 - start2)
+}
+
+/// Direction of travel of `vector`, in `-π..=π`. 0 for a zero vector
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::heading;
+/// use std::f32::consts::FRAC_PI_2;
+///
+/// assert_eq!(heading(Vec2::X), 0.);
+/// assert_eq!(heading(Vec2::new(0., 5.)), FRAC_PI_2);
+/// ```
+pub fn heading(vector: Vec2) -> f32 {
+    vector.to_angle()
+}
+
+/// Unit vector travelling in the direction `heading`, the inverse of `heading`
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::{from_heading, heading};
+///
+/// let velocity = Vec2::new(-3., 4.);
+/// let direction = from_heading(heading(velocity));
+/// assert!(direction.abs_diff_eq(velocity / 5., 1e-6));
+/// ```
+pub fn from_heading(heading: f32) -> Vec2 {
+    Vec2::from_angle(heading)
+}
+
+/// Ship `Rotation`, in radians, which points its mesh along `heading`
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::{heading, heading_to_rotation};
+///
+/// // meshes point up, so travelling up needs no rotation
+/// assert_eq!(heading_to_rotation(heading(Vec2::Y)), 0.);
+/// ```
+pub fn heading_to_rotation(heading: f32) -> f32 {
+    wrap_angle(heading - FRAC_PI_2)
+}
+
+/// Heading a ship with this `Rotation` is facing, the inverse of
+/// `heading_to_rotation`
+/// ```
+/// use boids::math::{heading_to_rotation, rotation_to_heading};
+///
+/// assert!((rotation_to_heading(heading_to_rotation(1.)) - 1.).abs() < 1e-6);
+/// ```
+pub fn rotation_to_heading(rotation: f32) -> f32 {
+    wrap_angle(rotation + FRAC_PI_2)
+}
+
+/// The same angle in `-π..π`, e.g. for the difference between two headings
+/// ```
+/// use boids::math::wrap_angle;
+/// use std::f32::consts::PI;
+///
+/// assert!((wrap_angle(1.5 * PI) + 0.5 * PI).abs() < 1e-6);
+/// assert!((wrap_angle(-2.5 * PI) + 0.5 * PI).abs() < 1e-6);
+/// assert_eq!(wrap_angle(0.25), 0.25);
+/// ```
+pub fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// Shortest displacement from `from` to `to` in a world centred on 0,0 whose
+/// edges wrap round, so leaving one side comes back in the other
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::toroidal_delta;
+///
+/// let half_size = Vec2::new(400., 300.);
+/// // across the left and right edges rather than through the middle
+/// let delta = toroidal_delta(Vec2::new(390., 0.), Vec2::new(-390., 0.), half_size);
+/// assert!(delta.abs_diff_eq(Vec2::new(20., 0.), 1e-3));
+/// // no shorter way round
+/// let delta = toroidal_delta(Vec2::ZERO, Vec2::new(100., -50.), half_size);
+/// assert_eq!(delta, Vec2::new(100., -50.));
+/// ```
+pub fn toroidal_delta(from: Vec2, to: Vec2, half_size: Vec2) -> Vec2 {
+    let size = half_size * 2.;
+    let delta = to - from;
+
+    // into -half_size..half_size on each axis
+    (delta + half_size).rem_euclid(size) - half_size
+}
+
+/// Length of `toroidal_delta`
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::toroidal_distance;
+///
+/// let half_size = Vec2::new(400., 300.);
+/// let distance = toroidal_distance(Vec2::new(0., 290.), Vec2::new(0., -290.), half_size);
+/// assert!((distance - 20.).abs() < 1e-3);
+/// ```
+pub fn toroidal_distance(from: Vec2, to: Vec2, half_size: Vec2) -> f32 {
+    toroidal_delta(from, to, half_size).length()
+}
+
+/// Closest point to `point` on the infinite line through `a` and `b`, the
+/// foot of the normal from `point`. `a` if they're the same point
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::project_onto_line;
+///
+/// let foot = project_onto_line(Vec2::new(15., 7.), Vec2::ZERO, Vec2::new(10., 0.));
+/// assert_eq!(foot, Vec2::new(15., 0.));
+/// ```
+pub fn project_onto_line(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let along = (b - a).normalize_or_zero();
+    a + along * (point - a).dot(along)
+}
+
+/// Closest point to `point` on the line segment from `a` to `b`
+/// ```
+/// use bevy::prelude::*;
+/// use boids::math::project_onto_segment;
+///
+/// let (a, b) = (Vec2::ZERO, Vec2::new(10., 0.));
+/// assert_eq!(project_onto_segment(Vec2::new(5., 7.), a, b), Vec2::new(5., 0.));
+/// // past the end
+/// assert_eq!(project_onto_segment(Vec2::new(15., 7.), a, b), b);
+/// ```
+pub fn project_onto_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared < f32::EPSILON {
+        return a;
+    }
+
+    let along = ((point - a).dot(ab) / length_squared).clamp(0., 1.);
+    a + ab * along
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{
+        from_heading, heading, heading_to_rotation, map_range, rotation_to_heading, toroidal_delta,
+        wrap_angle,
+    };
+    use bevy::prelude::*;
+    use proptest::prelude::*;
+    use std::f32::consts::PI;
+
+    fn vec2(range: f32) -> impl Strategy<Value = Vec2> {
+        (-range..range, -range..range).prop_map(|(x, y)| Vec2::new(x, y))
+    }
+
+    #[test]
+    fn map_range_should_not_divide_by_zero() {
+        assert_eq!(map_range(3., 2., 2., 10., 20.), 10.);
+    }
+
+    proptest! {
+        #[test]
+        fn map_range_should_round_trip(
+            value in -1000f32..1000.,
+            start in -100f32..100.,
+            length in 1f32..100.,
+        ) {
+            let mapped = map_range(value, start, start + length, 0., 1.);
+            let back = map_range(mapped, 0., 1., start, start + length);
+            prop_assert!((back - value).abs() < 1e-2, "{} -> {}", value, back);
+        }
+
+        #[test]
+        fn heading_should_round_trip(velocity in vec2(500.)) {
+            prop_assume!(velocity.length() > 1e-3);
+            let direction = from_heading(heading(velocity));
+            prop_assert!(direction.abs_diff_eq(velocity.normalize(), 1e-4));
+            let rotation = heading_to_rotation(heading(velocity));
+            prop_assert!((wrap_angle(rotation_to_heading(rotation) - heading(velocity))).abs() < 1e-4);
+        }
+
+        #[test]
+        fn wrap_angle_should_stay_within_half_turn(angle in -100f32..100.) {
+            let wrapped = wrap_angle(angle);
+            prop_assert!((-PI..=PI).contains(&wrapped));
+            // same direction
+            prop_assert!(from_heading(wrapped).abs_diff_eq(from_heading(angle), 1e-3));
+        }
+
+        #[test]
+        fn toroidal_delta_should_be_shortest_way_round(from in vec2(400.), to in vec2(400.)) {
+            let half_size = Vec2::splat(400.);
+            let delta = toroidal_delta(from, to, half_size);
+            prop_assert!(delta.x.abs() <= 400. && delta.y.abs() <= 400.);
+            prop_assert!(delta.length() <= from.distance(to) + 1e-3);
+
+            // lands on `to`, or a copy of it one world over
+            let offset = (from + delta - to) / 800.;
+            prop_assert!(offset.abs_diff_eq(offset.round(), 1e-3));
+        }
+    }
+}
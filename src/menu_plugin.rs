@@ -0,0 +1,326 @@
+use bevy::input::InputSystem;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::steering_plugin::Behaviour;
+use crate::steering_plugin::registry::BehaviourRegistry;
+
+/// Switching behaviour - a button, number key shortcut and command palette
+/// for everything in the `BehaviourRegistry`
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandPalette>()
+            .add_systems(Startup, setup)
+            // before anything else sees the keys typed into the palette
+            .add_systems(PreUpdate, command_palette_system.after(InputSystem))
+            .add_systems(
+                Update,
+                (
+                    button_handler_system,
+                    shortcut_system,
+                    button_colour_system,
+                    palette_text_system,
+                ),
+            );
+    }
+}
+
+/// Keys for each behaviour, in registry order
+pub const SHORTCUTS: [(KeyCode, &str); 12] = [
+    (KeyCode::Digit1, "1"),
+    (KeyCode::Digit2, "2"),
+    (KeyCode::Digit3, "3"),
+    (KeyCode::Digit4, "4"),
+    (KeyCode::Digit5, "5"),
+    (KeyCode::Digit6, "6"),
+    (KeyCode::Digit7, "7"),
+    (KeyCode::Digit8, "8"),
+    (KeyCode::Digit9, "9"),
+    (KeyCode::Digit0, "0"),
+    (KeyCode::Minus, "-"),
+    (KeyCode::Equal, "="),
+];
+
+/// Button of the current behaviour
+#[derive(Component)]
+struct SelectedOption;
+
+pub const NORMAL_BUTTON: Color = Color::srgb(0.95, 0.75, 0.35);
+const HOVERED_BUTTON: Color = Color::srgb(1., 0.83, 0.52);
+const HOVERED_SELECTED_BUTTON: Color = Color::srgb(1., 0.54, 0.8);
+const SELECTED_BUTTON: Color = Color::srgb(0.96, 0.05, 0.7);
+
+/// `/` to open, type to search behaviours, enter to switch to the highlighted one
+#[derive(Resource, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub query: String,
+    /// Index into the search results
+    pub selected: usize,
+}
+
+#[derive(Component)]
+struct PaletteNode;
+
+#[derive(Component)]
+struct PaletteText;
+
+fn setup(mut commands: Commands, registry: Res<BehaviourRegistry>) {
+    let buttons: Vec<Entity> = registry
+        .0
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let text = match SHORTCUTS.get(i) {
+                Some((_, key)) => format!("{key} {}", entry.name),
+                None => entry.name.to_string(),
+            };
+            commands.spawn(button(text, entry.behaviour.clone())).id()
+        })
+        .collect();
+
+    commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Start,
+            justify_content: JustifyContent::Start,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .add_children(&buttons);
+        });
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            left: Val::Percent(35.0),
+            width: Val::Px(360.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0., 0., 0., 0.8)),
+        PaletteNode,
+        children![(
+            Text::new(""),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            PaletteText,
+        )],
+    ));
+}
+
+fn button(text: String, behaviour: Behaviour) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(120.0),
+            height: Val::Px(35.0),
+            border: UiRect::all(Val::Px(3.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            margin: UiRect {
+                top: Val::Px(15.),
+                ..default()
+            },
+            ..default()
+        },
+        behaviour,
+        BorderRadius::MAX,
+        BackgroundColor(NORMAL_BUTTON),
+        BorderColor(Color::BLACK),
+        children![(
+            Text::new(text),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.24, 0.21, 0.19)),
+            TextShadow {
+                color: Color::srgb(0.64, 0.61, 0.59),
+                offset: Vec2 { x: 0.5, y: 0.5 }
+            },
+        )],
+    )
+}
+
+fn switch_to(
+    behaviour: &Behaviour,
+    current: &State<Behaviour>,
+    next_state: &mut NextState<Behaviour>,
+) {
+    // setting the same state would restart it
+    if behaviour != current.get() {
+        next_state.set(behaviour.clone());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn button_handler_system(
+    buttons: Query<(&Interaction, &Behaviour), (Changed<Interaction>, With<Button>)>,
+    current: Res<State<Behaviour>>,
+    mut next_state: ResMut<NextState<Behaviour>>,
+) {
+    for (interaction, behaviour) in &buttons {
+        if *interaction == Interaction::Pressed {
+            switch_to(behaviour, &current, &mut next_state);
+        }
+    }
+}
+
+fn shortcut_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<BehaviourRegistry>,
+    current: Res<State<Behaviour>>,
+    mut next_state: ResMut<NextState<Behaviour>>,
+) {
+    for (entry, (key, _)) in registry.0.iter().zip(SHORTCUTS) {
+        if keyboard.just_pressed(key) {
+            switch_to(&entry.behaviour, &current, &mut next_state);
+        }
+    }
+}
+
+// follows the state rather than clicks, so it's right whichever way the
+// behaviour was changed
+#[allow(clippy::type_complexity)]
+fn button_colour_system(
+    mut commands: Commands,
+    mut buttons: Query<
+        (
+            Entity,
+            &Interaction,
+            &Behaviour,
+            &mut BackgroundColor,
+            &mut BorderColor,
+            Has<SelectedOption>,
+        ),
+        With<Button>,
+    >,
+    current: Res<State<Behaviour>>,
+) {
+    for (entity, interaction, behaviour, mut bg, mut border, was_selected) in &mut buttons {
+        let is_selected = behaviour == current.get();
+        if is_selected && !was_selected {
+            commands.entity(entity).insert(SelectedOption);
+        } else if !is_selected && was_selected {
+            commands.entity(entity).remove::<SelectedOption>();
+        }
+
+        match (interaction, is_selected) {
+            (Interaction::Hovered, true) => {
+                border.0 = Color::WHITE;
+                *bg = HOVERED_SELECTED_BUTTON.into();
+            }
+            (Interaction::Hovered, false) => {
+                border.0 = Color::WHITE;
+                *bg = HOVERED_BUTTON.into();
+            }
+            (_, true) => {
+                *bg = SELECTED_BUTTON.into();
+                border.0 = Color::BLACK;
+            }
+            (_, false) => {
+                *bg = NORMAL_BUTTON.into();
+                border.0 = Color::BLACK;
+            }
+        }
+    }
+}
+
+fn command_palette_system(
+    mut palette: ResMut<CommandPalette>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut typed: EventReader<KeyboardInput>,
+    registry: Res<BehaviourRegistry>,
+    current: Res<State<Behaviour>>,
+    mut next_state: ResMut<NextState<Behaviour>>,
+) {
+    if !palette.open {
+        // only what's typed after opening counts
+        typed.clear();
+        if keyboard.just_pressed(KeyCode::Slash) {
+            *palette = CommandPalette {
+                open: true,
+                ..default()
+            };
+        }
+        return;
+    }
+
+    for event in typed.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(text) => {
+                palette.query.push_str(text);
+                palette.selected = 0;
+            }
+            Key::Backspace => {
+                palette.query.pop();
+                palette.selected = 0;
+            }
+            Key::ArrowDown => palette.selected += 1,
+            Key::ArrowUp => palette.selected = palette.selected.saturating_sub(1),
+            Key::Enter => {
+                if let Some(entry) = registry.search(&palette.query).get(palette.selected) {
+                    switch_to(&entry.behaviour, &current, &mut next_state);
+                }
+                palette.open = false;
+            }
+            Key::Escape => palette.open = false,
+            _ => {}
+        }
+    }
+
+    let results = registry.search(&palette.query).len();
+    palette.selected = palette.selected.min(results.saturating_sub(1));
+
+    // typing a name shouldn't also press every shortcut in it
+    keyboard.reset_all();
+}
+
+fn palette_text_system(
+    palette: Res<CommandPalette>,
+    registry: Res<BehaviourRegistry>,
+    mut node: Single<&mut Node, With<PaletteNode>>,
+    mut text: Single<&mut Text, With<PaletteText>>,
+) {
+    if !palette.open {
+        node.display = Display::None;
+        return;
+    }
+    node.display = Display::Flex;
+
+    let results: Vec<String> = registry
+        .search(&palette.query)
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let marker = if i == palette.selected { ">" } else { " " };
+            let key = registry
+                .position(&entry.behaviour)
+                .and_then(|position| SHORTCUTS.get(position))
+                .map_or(String::new(), |(_, key)| format!(" ({key})"));
+            format!("{marker} {}{key} - {}", entry.name, entry.description)
+        })
+        .collect();
+
+    text.0 = format!("/{}\n\n{}", palette.query, results.join("\n"));
+}
@@ -0,0 +1,105 @@
+// arrive at the mouse, and tell the rest of the game when a ship has got there
+use bevy::prelude::*;
+
+use crate::input_plugin::MousePos;
+use crate::steering_plugin::behaviours::{Arrive, Deceleration, Steer, VehicleQuery};
+use crate::steering_plugin::forces::SteeringQuery;
+use crate::steering_plugin::{Ship, WorldBounds};
+
+/// How ships arrive, set with `--deceleration`, `--slowing-radius`,
+/// `--arrive-tolerance` and `--settle-speed`
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct ArriveSettings {
+    pub deceleration: Deceleration,
+    /// Where linear and quadratic deceleration start slowing down
+    pub slowing_radius: f32,
+    /// Closer than this to the target counts as there
+    pub tolerance: f32,
+    /// Slower than this, as well as within `tolerance`, counts as settled
+    pub settle_speed: f32,
+}
+
+impl Default for ArriveSettings {
+    fn default() -> Self {
+        let arrive = Arrive::default();
+
+        Self {
+            deceleration: arrive.deceleration,
+            slowing_radius: arrive.slowing_radius,
+            tolerance: arrive.tolerance,
+            settle_speed: 5.,
+        }
+    }
+}
+
+impl ArriveSettings {
+    fn arrive(&self, target: Vec2, delta: f32) -> Arrive {
+        Arrive {
+            target,
+            slowing_radius: self.slowing_radius,
+            deceleration: self.deceleration,
+            tolerance: self.tolerance,
+            delta,
+        }
+    }
+}
+
+/// Sent once when a ship settles at its arrive target
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct Arrived {
+    pub ship: Entity,
+    pub target: Vec2,
+}
+
+/// On ships which have arrived, until they move away from the target again
+#[derive(Component)]
+pub struct Settled;
+
+pub fn arrive_system(
+    mut query: Query<(SteeringQuery, VehicleQuery), With<Ship>>,
+    target: Res<MousePos>,
+    settings: Res<ArriveSettings>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+) {
+    query.par_iter_mut().for_each(|(mut steering, vehicle)| {
+        if vehicle.is_due() {
+            // held until the ship is next due
+            let delta = time.delta_secs() * vehicle.interval() as f32;
+            let arrive = settings.arrive(target.0, delta);
+            steering.add("arrive", arrive.steer(&vehicle.snapshot(&bounds)));
+        }
+    });
+}
+
+// after steering is applied, so it sees the velocity the ship is left with
+pub fn arrived_system(
+    mut commands: Commands,
+    ships: Query<(Entity, VehicleQuery, Has<Settled>), With<Ship>>,
+    target: Res<MousePos>,
+    settings: Res<ArriveSettings>,
+    bounds: Res<WorldBounds>,
+    mut arrived: EventWriter<Arrived>,
+) {
+    for (ship, vehicle, settled) in &ships {
+        let vehicle = vehicle.snapshot(&bounds);
+        let there = vehicle.space.distance(vehicle.position, target.0) <= settings.tolerance;
+        let still = vehicle.velocity.length() <= settings.settle_speed;
+
+        if there && still && !settled {
+            commands.entity(ship).insert(Settled);
+            arrived.write(Arrived {
+                ship,
+                target: target.0,
+            });
+        } else if !there && settled {
+            commands.entity(ship).remove::<Settled>();
+        }
+    }
+}
+
+pub fn clean_up_arrive(mut commands: Commands, ships: Query<Entity, With<Settled>>) {
+    for ship in &ships {
+        commands.entity(ship).remove::<Settled>();
+    }
+}
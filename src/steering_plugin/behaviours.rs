@@ -0,0 +1,855 @@
+// the steering behaviours as pure functions of a ship and what it's steering
+// relative to, so they can be tested without an app. The systems gather the
+// context, call these and add the result to each ship's `Steering`
+use avian2d::prelude::*;
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use bevy::tasks::{ComputeTaskPool, ParallelSlice, TaskPool};
+use clap::ValueEnum;
+
+use crate::math::{
+    from_heading, heading, map_range, project_onto_segment, set_magnitude, toroidal_delta,
+};
+use crate::steering_plugin::forces::MaxSteeringForce;
+use crate::steering_plugin::lod::{LodState, is_due};
+use crate::steering_plugin::{WorldBounds, WrapEdges};
+
+/// What a behaviour knows about the ship it's steering. Not to be confused
+/// with `vehicle::Vehicle`, the car and boat movement models
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VehicleSnapshot {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub max_speed: f32,
+    /// Not applied by the behaviours - their total is limited to this by
+    /// `limit_steering_system`
+    pub max_force: f32,
+    pub space: Space,
+}
+
+/// The world a vehicle moves in, which decides which way round is shortest
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Space {
+    #[default]
+    Plane,
+    /// Centred on 0,0 with edges which wrap round, as for ships with `WrapEdges`
+    Torus { half_size: Vec2 },
+}
+
+impl Space {
+    /// Shortest displacement from `from` to `to`
+    pub fn delta(&self, from: Vec2, to: Vec2) -> Vec2 {
+        match self {
+            Space::Plane => to - from,
+            Space::Torus { half_size } => toroidal_delta(from, to, *half_size),
+        }
+    }
+
+    pub fn distance(&self, from: Vec2, to: Vec2) -> f32 {
+        self.delta(from, to).length()
+    }
+
+    /// Whichever copy of `to` is closest to `from`, e.g. to draw a line
+    /// between them which doesn't cross the whole world
+    pub fn nearest(&self, from: Vec2, to: Vec2) -> Vec2 {
+        from + self.delta(from, to)
+    }
+
+    /// The copy of `point` inside the world, e.g. to draw a prediction which
+    /// has gone over an edge
+    pub fn wrap(&self, point: Vec2) -> Vec2 {
+        match self {
+            Space::Plane => point,
+            // the world is centred on 0,0
+            Space::Torus { half_size } => toroidal_delta(Vec2::ZERO, point, *half_size),
+        }
+    }
+}
+
+/// Components making up a `VehicleSnapshot`, and whether it steers this tick
+#[derive(QueryData)]
+pub struct VehicleQuery {
+    pub position: &'static Position,
+    pub velocity: &'static LinearVelocity,
+    pub max_speed: &'static MaxLinearSpeed,
+    pub max_force: &'static MaxSteeringForce,
+    pub lod: Option<&'static LodState>,
+    pub wraps: Has<WrapEdges>,
+}
+
+impl VehicleQueryItem<'_> {
+    /// False when level of detail has it skip steering this tick
+    pub fn is_due(&self) -> bool {
+        is_due(self.lod)
+    }
+
+    /// Ticks until it next steers, 1 without level of detail
+    pub fn interval(&self) -> u32 {
+        self.lod.map_or(1, |lod| lod.interval)
+    }
+
+    /// Wrapping round `bounds` if the ship has `WrapEdges`
+    pub fn snapshot(&self, bounds: &WorldBounds) -> VehicleSnapshot {
+        VehicleSnapshot {
+            position: self.position.0,
+            velocity: self.velocity.0,
+            max_speed: self.max_speed.0,
+            max_force: self.max_force.0,
+            space: if self.wraps {
+                Space::Torus {
+                    half_size: bounds.half_size,
+                }
+            } else {
+                Space::Plane
+            },
+        }
+    }
+}
+
+/// A steering behaviour, holding whatever it steers relative to
+pub trait Steer {
+    /// Force to turn `vehicle`'s velocity into the one this behaviour wants
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2;
+}
+
+/// Items handed to each task by `par_map`
+const BATCH_SIZE: usize = 256;
+
+/// `f` of every item, split into batches over the compute task pool. Results
+/// are in the same order as `items`, and as long as `f` only depends on its
+/// item they're identical to mapping sequentially
+pub fn par_map<T: Sync, R: Send + 'static>(
+    items: &[T],
+    f: impl Fn(&T) -> R + Send + Sync,
+) -> Vec<R> {
+    let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+
+    items
+        .par_chunk_map(pool, BATCH_SIZE, |_, batch| {
+            batch.iter().map(&f).collect::<Vec<R>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// https://natureofcode.com/autonomous-agents/#example-51-seeking-a-target
+/// Full speed towards the target
+pub struct Seek {
+    pub target: Vec2,
+}
+
+impl Steer for Seek {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        let desired = vehicle.space.delta(vehicle.position, self.target);
+        set_magnitude(desired, vehicle.max_speed) - vehicle.velocity
+    }
+}
+
+/// Full speed directly away from the threat
+pub struct Flee {
+    pub threat: Vec2,
+}
+
+impl Steer for Flee {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        let desired = -vehicle.space.delta(vehicle.position, self.threat);
+        set_magnitude(desired, vehicle.max_speed) - vehicle.velocity
+    }
+}
+
+/// How arrive slows down as it nears its target
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Deceleration {
+    /// Speed falls in proportion to the distance left across the slowing radius
+    Linear,
+    /// Brakes evenly across the slowing radius, so speed falls with the square
+    /// root of the distance left. Stops exactly if the ship can brake that hard
+    #[default]
+    Quadratic,
+    // Buckland, Programming Game AI by Example, ch. 3. These ignore the slowing
+    // radius - the speed is the distance left over a time to get there
+    /// Buckland's fast, aiming to be there in 0.3s
+    Fast,
+    /// Buckland's normal, 0.6s
+    Normal,
+    /// Buckland's slow, 0.9s
+    Slow,
+}
+
+impl Deceleration {
+    /// Speed wanted `distance` from the target, at most `max_speed`
+    pub fn speed(self, distance: f32, slowing_radius: f32, max_speed: f32) -> f32 {
+        let speed = match self {
+            Self::Linear if distance < slowing_radius => {
+                map_range(distance, 0., slowing_radius, 0., max_speed)
+            }
+            Self::Quadratic if distance < slowing_radius => {
+                max_speed * (distance / slowing_radius).sqrt()
+            }
+            Self::Linear | Self::Quadratic => max_speed,
+            Self::Fast => distance / 0.3,
+            Self::Normal => distance / 0.6,
+            Self::Slow => distance / 0.9,
+        };
+
+        speed.min(max_speed)
+    }
+}
+
+/// Seek, slowing down near the target and stopping within `tolerance` of it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arrive {
+    pub target: Vec2,
+    pub slowing_radius: f32,
+    pub deceleration: Deceleration,
+    /// Closer than this counts as there, so the ship wants to stop
+    pub tolerance: f32,
+    /// Seconds the steering has to reach the velocity wanted, until arrive next
+    /// runs for the ship. The other behaviours always take 1, which brakes too
+    /// late to stop on the spot. Defaults to one fixed tick
+    pub delta: f32,
+}
+
+impl Default for Arrive {
+    fn default() -> Self {
+        Self {
+            target: Vec2::ZERO,
+            slowing_radius: 100.,
+            deceleration: Deceleration::default(),
+            tolerance: 2.,
+            delta: 1. / 64.,
+        }
+    }
+}
+
+impl Arrive {
+    pub fn desired_velocity(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        let offset = vehicle.space.delta(vehicle.position, self.target);
+        let distance = offset.length();
+        if distance <= self.tolerance {
+            return Vec2::ZERO;
+        }
+
+        let speed = self
+            .deceleration
+            .speed(distance, self.slowing_radius, vehicle.max_speed)
+            // not past the target before the next update
+            .min(distance / self.delta);
+
+        set_magnitude(offset, speed)
+    }
+}
+
+impl Steer for Arrive {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        (self.desired_velocity(vehicle) - vehicle.velocity) / self.delta
+    }
+}
+
+// https://natureofcode.com/autonomous-agents/#exercise-54
+/// Seek a point on a circle ahead of the ship. Moving the point round the
+/// circle a little each tick (`theta`) makes the ship meander
+pub struct Wander {
+    /// How far ahead the circle is
+    pub distance: f32,
+    pub radius: f32,
+    /// Angle round the circle, relative to the ship's heading
+    pub theta: f32,
+}
+
+impl Wander {
+    pub fn centre(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        vehicle.position + set_magnitude(vehicle.velocity, self.distance)
+    }
+
+    pub fn target(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        let theta = self.theta + heading(vehicle.velocity);
+
+        self.centre(vehicle) + from_heading(theta) * self.radius
+    }
+}
+
+impl Steer for Wander {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        Seek {
+            target: self.target(vehicle),
+        }
+        .steer(vehicle)
+    }
+}
+
+/// where the target will be by the time we could reach it, assuming it keeps
+/// going the same way
+pub fn predict_position(
+    vehicle: &VehicleSnapshot,
+    target_position: Vec2,
+    target_velocity: Vec2,
+) -> Vec2 {
+    let time_ahead = if vehicle.max_speed > 0. {
+        vehicle.space.distance(vehicle.position, target_position) / vehicle.max_speed
+    } else {
+        0.
+    };
+
+    target_position + target_velocity * time_ahead
+}
+
+// https://www.red3d.com/cwr/steer/PursueEvade.html
+/// Seek the predicted position of a moving target
+pub struct Pursue {
+    pub target_position: Vec2,
+    pub target_velocity: Vec2,
+}
+
+impl Pursue {
+    pub fn prediction(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        predict_position(vehicle, self.target_position, self.target_velocity)
+    }
+}
+
+impl Steer for Pursue {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        Seek {
+            target: self.prediction(vehicle),
+        }
+        .steer(vehicle)
+    }
+}
+
+/// Flee the predicted position of a moving threat
+pub struct Evade {
+    pub threat_position: Vec2,
+    pub threat_velocity: Vec2,
+}
+
+impl Evade {
+    pub fn prediction(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        predict_position(vehicle, self.threat_position, self.threat_velocity)
+    }
+}
+
+impl Steer for Evade {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        Flee {
+            threat: self.prediction(vehicle),
+        }
+        .steer(vehicle)
+    }
+}
+
+// https://natureofcode.com/autonomous-agents/#path-following
+/// Stay within `radius` of a looping path, heading round it in order
+pub struct FollowPath<'a> {
+    pub points: &'a [Vec2],
+    pub radius: f32,
+    /// How far ahead the ship looks, both for where it'll be and for where
+    /// to aim along the path
+    pub look_ahead: f32,
+}
+
+/// Where a ship is heading relative to the path
+#[derive(Debug, PartialEq)]
+pub struct PathProjection {
+    /// Where the ship will be
+    pub future: Vec2,
+    /// Closest point on the path to `future`
+    pub normal: Vec2,
+    /// Where to aim, a little further along the path from `normal`
+    pub target: Vec2,
+    pub distance: f32,
+}
+
+impl FollowPath<'_> {
+    /// None if there's no path
+    pub fn project(&self, vehicle: &VehicleSnapshot) -> Option<PathProjection> {
+        let points = self.points;
+        let future = vehicle.position + set_magnitude(vehicle.velocity, self.look_ahead);
+        let mut closest: Option<PathProjection> = None;
+
+        for i in 0..points.len() {
+            // wrap around
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            let normal = project_onto_segment(future, a, b);
+
+            let distance = future.distance(normal);
+            if closest.as_ref().is_none_or(|c| distance < c.distance) {
+                closest = Some(PathProjection {
+                    future,
+                    normal,
+                    target: normal + set_magnitude(b - a, self.look_ahead),
+                    distance,
+                });
+            }
+        }
+
+        closest
+    }
+}
+
+impl Steer for FollowPath<'_> {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        match self.project(vehicle) {
+            // only steer when drifting off the path
+            Some(projection) if projection.distance > self.radius => Seek {
+                target: projection.target,
+            }
+            .steer(vehicle),
+            _ => Vec2::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::behaviours::{
+        Arrive, Deceleration, Evade, Flee, FollowPath, Pursue, Seek, Space, Steer, VehicleSnapshot,
+        Wander, par_map,
+    };
+    use crate::steering_plugin::flock::{Align, Cohere, Neighbours, Separate};
+    use bevy::prelude::*;
+    use proptest::prelude::*;
+
+    fn vehicle(position: Vec2, velocity: Vec2, max_speed: f32) -> VehicleSnapshot {
+        VehicleSnapshot {
+            position,
+            velocity,
+            max_speed,
+            max_force: 400.,
+            space: Space::Plane,
+        }
+    }
+
+    const SQUARE: [Vec2; 4] = [
+        Vec2::new(-100., -100.),
+        Vec2::new(100., -100.),
+        Vec2::new(100., 100.),
+        Vec2::new(-100., 100.),
+    ];
+
+    #[test]
+    fn seek_should_return_vec2() {
+        let velocity = Vec2::new(180.0, -123.0);
+        let position = Vec2::new(14.0, 18.0);
+
+        let seek =
+            |target, max_speed| Seek { target }.steer(&vehicle(position, velocity, max_speed));
+
+        assert_eq!(
+            seek(Vec2::new(10.0, -12.0), 14.),
+            Vec2::new(-181.8503, 109.12281)
+        );
+        assert_eq!(
+            seek(Vec2::new(-102.0, 130.0), 14.),
+            Vec2::new(-190.07162, 132.72432)
+        );
+        assert_eq!(
+            seek(Vec2::new(10.0, -12.0), 2.),
+            Vec2::new(-180.26433, 121.01755)
+        );
+    }
+
+    #[test]
+    fn flee_should_head_directly_away() {
+        let ship = vehicle(Vec2::ZERO, Vec2::new(0., 10.), 50.);
+
+        assert_eq!(
+            Flee {
+                threat: Vec2::new(10., 0.)
+            }
+            .steer(&ship),
+            Vec2::new(-50., -10.)
+        );
+    }
+
+    #[test]
+    fn arrive_should_only_slow_down_near_target() {
+        let ship = vehicle(Vec2::ZERO, Vec2::ZERO, 200.);
+        let arrive = |target| {
+            Arrive {
+                target,
+                slowing_radius: 60.,
+                deceleration: Deceleration::Linear,
+                ..default()
+            }
+            .desired_velocity(&ship)
+        };
+
+        assert_eq!(arrive(Vec2::new(500., 0.)), Vec2::new(200., 0.));
+        // half way in, half speed
+        assert_eq!(arrive(Vec2::new(30., 0.)), Vec2::new(100., 0.));
+        assert_eq!(arrive(Vec2::ZERO), Vec2::ZERO);
+    }
+
+    #[test]
+    fn deceleration_should_slow_down_by_profile() {
+        let speed = |deceleration: Deceleration, distance| deceleration.speed(distance, 100., 200.);
+
+        assert_eq!(speed(Deceleration::Linear, 25.), 50.);
+        // braking evenly, so still faster a quarter of the way in
+        assert_eq!(speed(Deceleration::Quadratic, 25.), 100.);
+        assert_eq!(speed(Deceleration::Quadratic, 100.), 200.);
+        // Buckland's ignore the radius
+        assert!((speed(Deceleration::Fast, 30.) - 100.).abs() < 1e-3);
+        assert!((speed(Deceleration::Normal, 30.) - 50.).abs() < 1e-3);
+        assert!((speed(Deceleration::Slow, 45.) - 50.).abs() < 1e-3);
+        assert_eq!(speed(Deceleration::Slow, 1000.), 200.);
+    }
+
+    #[test]
+    fn arrive_should_stop_within_tolerance() {
+        let ship = vehicle(Vec2::new(1., 1.), Vec2::new(20., 0.), 200.);
+        let arrive = Arrive {
+            target: Vec2::ZERO,
+            tolerance: 2.,
+            ..default()
+        };
+
+        assert_eq!(arrive.desired_velocity(&ship), Vec2::ZERO);
+        // all of the velocity taken off by the next update
+        assert_eq!(arrive.steer(&ship) * arrive.delta, -ship.velocity);
+    }
+
+    #[test]
+    fn wander_target_should_be_on_circle_ahead() {
+        let ship = vehicle(Vec2::new(10., 10.), Vec2::new(0., 5.), 100.);
+        let wander = Wander {
+            distance: 100.,
+            radius: 50.,
+            theta: 0.,
+        };
+
+        assert_eq!(wander.centre(&ship), Vec2::new(10., 110.));
+        // theta is relative to the heading, so 0 is straight ahead
+        assert!(wander.target(&ship).abs_diff_eq(Vec2::new(10., 160.), 1e-3));
+    }
+
+    // a ship just inside the right edge, with a target just inside the left
+    fn by_right_edge() -> VehicleSnapshot {
+        VehicleSnapshot {
+            space: Space::Torus {
+                half_size: Vec2::new(400., 300.),
+            },
+            ..vehicle(Vec2::new(390., 0.), Vec2::ZERO, 100.)
+        }
+    }
+
+    #[test]
+    fn seek_and_flee_should_go_the_short_way_round_wrapping_edges() {
+        let ship = by_right_edge();
+        let target = Vec2::new(-390., 0.);
+
+        assert_eq!(Seek { target }.steer(&ship), Vec2::new(100., 0.));
+        assert_eq!(Flee { threat: target }.steer(&ship), Vec2::new(-100., 0.));
+        // without wrapping, the other way
+        let plane = VehicleSnapshot {
+            space: Space::Plane,
+            ..ship
+        };
+        assert_eq!(Seek { target }.steer(&plane), Vec2::new(-100., 0.));
+    }
+
+    #[test]
+    fn pursue_should_predict_across_wrapping_edges() {
+        let pursue = Pursue {
+            target_position: Vec2::new(-390., 0.),
+            target_velocity: Vec2::new(0., 50.),
+        };
+
+        // 20 away round the edge, so 0.2 seconds rather than 7.8
+        let prediction = pursue.prediction(&by_right_edge());
+        assert!(prediction.abs_diff_eq(Vec2::new(-390., 10.), 1e-3));
+        assert!(pursue.steer(&by_right_edge()).x > 0.);
+    }
+
+    #[test]
+    fn space_nearest_should_be_copy_of_point_closest_to_from() {
+        let torus = Space::Torus {
+            half_size: Vec2::new(400., 300.),
+        };
+        let nearest = torus.nearest(Vec2::new(390., 290.), Vec2::new(-390., -290.));
+
+        assert!(nearest.abs_diff_eq(Vec2::new(410., 310.), 1e-3));
+        assert_eq!(
+            Space::Plane.nearest(Vec2::ZERO, Vec2::new(-390., 0.)),
+            Vec2::new(-390., 0.)
+        );
+    }
+
+    #[test]
+    fn space_wrap_should_bring_point_back_inside() {
+        let torus = Space::Torus {
+            half_size: Vec2::new(400., 300.),
+        };
+
+        assert!(
+            torus
+                .wrap(Vec2::new(410., -320.))
+                .abs_diff_eq(Vec2::new(-390., 280.), 1e-3)
+        );
+        assert_eq!(torus.wrap(Vec2::new(10., 20.)), Vec2::new(10., 20.));
+        assert_eq!(Space::Plane.wrap(Vec2::new(410., 0.)), Vec2::new(410., 0.));
+    }
+
+    #[test]
+    fn pursue_should_lead_moving_target() {
+        let ship = vehicle(Vec2::ZERO, Vec2::ZERO, 100.);
+        let pursue = Pursue {
+            target_position: Vec2::new(200., 0.),
+            target_velocity: Vec2::new(0., 50.),
+        };
+
+        // 2 seconds away, in which time the target moves 100 up
+        assert_eq!(pursue.prediction(&ship), Vec2::new(200., 100.));
+        assert_eq!(
+            pursue.steer(&ship),
+            Seek {
+                target: Vec2::new(200., 100.)
+            }
+            .steer(&ship)
+        );
+    }
+
+    #[test]
+    fn evade_should_flee_prediction() {
+        let ship = vehicle(Vec2::ZERO, Vec2::ZERO, 100.);
+        let evade = Evade {
+            threat_position: Vec2::new(200., 0.),
+            threat_velocity: Vec2::new(-100., 0.),
+        };
+
+        assert_eq!(evade.prediction(&ship), Vec2::ZERO);
+        // right on top of us, so no way to tell which way to go
+        assert_eq!(evade.steer(&ship), Vec2::ZERO);
+
+        let evade = Evade {
+            threat_position: Vec2::new(200., 0.),
+            threat_velocity: Vec2::new(0., 100.),
+        };
+        assert!(evade.steer(&ship).x < 0.);
+    }
+
+    #[test]
+    fn follow_path_should_steer_back_onto_path() {
+        let path = FollowPath {
+            points: &SQUARE,
+            radius: 10.,
+            look_ahead: 15.,
+        };
+
+        // on the bottom edge, heading along it
+        let on_path = vehicle(Vec2::new(0., -100.), Vec2::new(50., 0.), 100.);
+        let projection = path.project(&on_path).unwrap();
+        assert_eq!(projection.normal, Vec2::new(15., -100.));
+        assert_eq!(projection.target, Vec2::new(30., -100.));
+        assert_eq!(path.steer(&on_path), Vec2::ZERO);
+
+        // well inside the square
+        let off_path = vehicle(Vec2::new(0., -50.), Vec2::new(50., 0.), 100.);
+        assert!(path.steer(&off_path).y < 0.);
+
+        let no_path = FollowPath {
+            points: &[],
+            radius: 10.,
+            look_ahead: 15.,
+        };
+        assert_eq!(no_path.steer(&off_path), Vec2::ZERO);
+    }
+
+    #[test]
+    fn follow_path_should_use_nearest_point_on_a_segment() {
+        let path = FollowPath {
+            points: &SQUARE,
+            radius: 10.,
+            look_ahead: 15.,
+        };
+
+        // off the end of the bottom and right edges, so the corner is closest
+        let past_corner = vehicle(Vec2::new(120., -130.), Vec2::new(0., 50.), 100.);
+        let projection = path.project(&past_corner).unwrap();
+        assert_eq!(projection.normal, Vec2::new(100., -100.));
+        assert_eq!(
+            projection.distance,
+            Vec2::new(120., -115.).distance(projection.normal)
+        );
+
+        // beside the middle of the left edge, aiming along it in its direction
+        let beside = vehicle(Vec2::new(-110., -20.), Vec2::new(0., -50.), 100.);
+        let projection = path.project(&beside).unwrap();
+        assert_eq!(projection.normal, Vec2::new(-100., -35.));
+        assert_eq!(projection.target, Vec2::new(-100., -50.));
+    }
+
+    #[test]
+    fn par_map_should_match_sequential_flocking() {
+        // spread out, but close enough for plenty of neighbours
+        let vehicles: Vec<VehicleSnapshot> = (0..2000)
+            .map(|i| {
+                let i = i as f32;
+                let position = Vec2::from_angle(i * 2.4) * i.sqrt() * 8.;
+                vehicle(position, Vec2::from_angle(i) * 100., 250.)
+            })
+            .collect();
+        let neighbours: Neighbours = vehicles
+            .iter()
+            .map(|vehicle| (vehicle.position, vehicle.velocity))
+            .collect();
+        let separate = Separate {
+            neighbours: &neighbours,
+            distance: 25.,
+        };
+        let align = Align {
+            neighbours: &neighbours,
+            radius: 60.,
+        };
+        let cohere = Cohere {
+            neighbours: &neighbours,
+            radius: 60.,
+        };
+        let flock = |vehicle: &VehicleSnapshot| {
+            separate.steer(vehicle) * 1.5 + align.steer(vehicle) + cohere.steer(vehicle)
+        };
+
+        let sequential: Vec<Vec2> = vehicles.iter().map(flock).collect();
+        let parallel = par_map(&vehicles, flock);
+
+        assert_eq!(parallel, sequential);
+        assert!(sequential.iter().any(|steering| *steering != Vec2::ZERO));
+    }
+
+    fn vec2(range: f32) -> impl Strategy<Value = Vec2> {
+        (-range..range, -range..range).prop_map(|(x, y)| Vec2::new(x, y))
+    }
+
+    fn any_vehicle() -> impl Strategy<Value = VehicleSnapshot> {
+        (vec2(1000.), vec2(300.), 1f32..400.)
+            .prop_map(|(position, velocity, max_speed)| vehicle(position, velocity, max_speed))
+    }
+
+    // the velocity a behaviour wants is what it steers towards
+    fn desired(steering: Vec2, vehicle: &VehicleSnapshot) -> Vec2 {
+        vehicle.velocity + steering
+    }
+
+    proptest! {
+        #[test]
+        fn seek_should_want_full_speed_towards_target(ship in any_vehicle(), target in vec2(1000.)) {
+            prop_assume!(ship.position.distance(target) > 0.01);
+            let desired = desired(Seek { target }.steer(&ship), &ship);
+
+            prop_assert!((desired.length() - ship.max_speed).abs() < 0.01 * ship.max_speed);
+            prop_assert!(desired.dot(target - ship.position) > 0.);
+        }
+
+        #[test]
+        fn flee_should_want_full_speed_away(ship in any_vehicle(), threat in vec2(1000.)) {
+            prop_assume!(ship.position.distance(threat) > 0.01);
+            let desired = desired(Flee { threat }.steer(&ship), &ship);
+
+            prop_assert!((desired.length() - ship.max_speed).abs() < 0.01 * ship.max_speed);
+            prop_assert!(desired.dot(threat - ship.position) < 0.);
+        }
+
+        #[test]
+        fn arrive_should_head_for_target(
+            ship in any_vehicle(),
+            target in vec2(1000.),
+            slowing_radius in 0f32..200.,
+            deceleration in prop::sample::select(vec![
+                Deceleration::Linear,
+                Deceleration::Quadratic,
+                Deceleration::Fast,
+                Deceleration::Normal,
+                Deceleration::Slow,
+            ]),
+            delta in 0.001f32..1.,
+        ) {
+            let distance = ship.position.distance(target);
+            let arrive = Arrive { target, slowing_radius, deceleration, tolerance: 2., delta };
+            let desired = arrive.desired_velocity(&ship);
+
+            prop_assert!(desired.is_finite());
+            prop_assert!(desired.length() <= ship.max_speed * 1.001);
+            // never far enough to pass the target before the next update
+            prop_assert!(desired.length() * delta <= distance * 1.001);
+            if distance > arrive.tolerance {
+                prop_assert!(desired.dot(target - ship.position) > 0.);
+            } else {
+                prop_assert_eq!(desired, Vec2::ZERO);
+            }
+            if deceleration == Deceleration::Linear && distance >= slowing_radius && distance >= ship.max_speed * delta {
+                prop_assert!((desired.length() - ship.max_speed).abs() < 0.01 * ship.max_speed);
+            }
+        }
+
+        #[test]
+        fn wander_target_should_stay_on_circle(ship in any_vehicle(), theta in -10f32..10., distance in 0f32..200., radius in 0f32..100.) {
+            let wander = Wander { distance, radius, theta };
+            let desired = desired(wander.steer(&ship), &ship);
+
+            prop_assert!((wander.target(&ship).distance(wander.centre(&ship)) - radius).abs() < 0.01);
+            prop_assert!(desired.length() <= ship.max_speed * 1.001);
+        }
+
+        #[test]
+        fn pursue_and_evade_should_match_seek_and_flee_for_still_targets(ship in any_vehicle(), target in vec2(1000.)) {
+            let pursue = Pursue { target_position: target, target_velocity: Vec2::ZERO };
+            let evade = Evade { threat_position: target, threat_velocity: Vec2::ZERO };
+
+            prop_assert_eq!(pursue.steer(&ship), Seek { target }.steer(&ship));
+            prop_assert_eq!(evade.steer(&ship), Flee { threat: target }.steer(&ship));
+        }
+
+        #[test]
+        fn pursue_should_aim_where_target_is_going(ship in any_vehicle(), target in vec2(1000.), target_velocity in vec2(300.)) {
+            let pursue = Pursue { target_position: target, target_velocity };
+            let prediction = pursue.prediction(&ship);
+            let time_ahead = ship.position.distance(target) / ship.max_speed;
+
+            prop_assert!(prediction.abs_diff_eq(target + target_velocity * time_ahead, 0.01));
+            prop_assert!(desired(pursue.steer(&ship), &ship).length() <= ship.max_speed * 1.001);
+        }
+
+        #[test]
+        fn follow_path_should_aim_at_the_path(ship in any_vehicle()) {
+            let path = FollowPath { points: &SQUARE, radius: 10., look_ahead: 15. };
+            let projection = path.project(&ship).unwrap();
+            let steering = path.steer(&ship);
+
+            // normals are always on an edge of the square
+            let on_edge = projection.normal.x.abs().max(projection.normal.y.abs());
+            prop_assert!((on_edge - 100.).abs() < 0.01);
+            prop_assert!(projection.normal.abs().max_element() <= 100.01);
+
+            if projection.distance <= path.radius {
+                prop_assert_eq!(steering, Vec2::ZERO);
+            } else {
+                prop_assert!(desired(steering, &ship).length() <= ship.max_speed * 1.001);
+            }
+        }
+
+        #[test]
+        fn behaviours_should_be_finite_for_stopped_ships(position in vec2(1000.), max_speed in 0f32..400.) {
+            // stopped, on top of everything it's steering relative to
+            let ship = vehicle(position, Vec2::ZERO, max_speed);
+            let wander = Wander { distance: 100., radius: 50., theta: 1. };
+            let path_points = [position, position];
+            let path = FollowPath { points: &path_points, radius: 10., look_ahead: 15. };
+
+            let steerings = [
+                Seek { target: position }.steer(&ship),
+                Flee { threat: position }.steer(&ship),
+                Arrive { target: position, ..default() }.steer(&ship),
+                wander.steer(&ship),
+                Pursue { target_position: position, target_velocity: Vec2::ONE }.steer(&ship),
+                Evade { threat_position: position, threat_velocity: Vec2::ONE }.steer(&ship),
+                path.steer(&ship),
+            ];
+
+            for steering in steerings {
+                prop_assert!(steering.is_finite());
+            }
+        }
+    }
+}
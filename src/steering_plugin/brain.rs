@@ -0,0 +1,158 @@
+// small feed-forward neural network used as a steering brain
+// https://natureofcode.com/neuroevolution/
+use rand::Rng;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Fully connected network with tanh activation. `layers` is the number of
+/// neurons in each layer, including inputs and outputs
+#[derive(Clone, Debug, PartialEq)]
+pub struct Brain {
+    pub layers: Vec<usize>,
+    // for each layer in turn, a bias then weights for every input, per neuron
+    pub weights: Vec<f32>,
+}
+
+fn weight_count(layers: &[usize]) -> usize {
+    layers.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum()
+}
+
+impl Brain {
+    pub fn random(layers: Vec<usize>, rng: &mut impl Rng) -> Self {
+        let weights = (0..weight_count(&layers))
+            .map(|_| rng.random_range(-1.0..1.))
+            .collect();
+
+        Self { layers, weights }
+    }
+
+    pub fn predict(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut values = inputs.to_vec();
+        let mut weights = self.weights.iter();
+
+        for pair in self.layers.windows(2) {
+            values = (0..pair[1])
+                .map(|_| {
+                    let bias = *weights.next().unwrap_or(&0.);
+                    let sum: f32 = values
+                        .iter()
+                        .map(|value| value * weights.next().unwrap_or(&0.))
+                        .sum();
+                    (bias + sum).tanh()
+                })
+                .collect();
+        }
+
+        values
+    }
+
+    /// each weight comes from one parent or the other
+    pub fn crossover(&self, other: &Brain, rng: &mut impl Rng) -> Self {
+        let weights = self
+            .weights
+            .iter()
+            .zip(&other.weights)
+            .map(|(a, b)| if rng.random_bool(0.5) { *a } else { *b })
+            .collect();
+
+        Self {
+            layers: self.layers.clone(),
+            weights,
+        }
+    }
+
+    /// `rate` is the chance of each weight changing
+    pub fn mutate(&mut self, rate: f32, rng: &mut impl Rng) {
+        for weight in &mut self.weights {
+            if rng.random_bool(rate as f64) {
+                *weight += rng.random_range(-0.5..0.5);
+            }
+        }
+    }
+
+    /// Plain text - layer sizes on the first line, weights on the second
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let layers: Vec<String> = self.layers.iter().map(|l| l.to_string()).collect();
+        let weights: Vec<String> = self.weights.iter().map(|w| w.to_string()).collect();
+
+        fs::write(
+            path,
+            format!("{}\n{}\n", layers.join(" "), weights.join(" ")),
+        )
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+
+        let layers = lines
+            .next()
+            .ok_or_else(|| invalid("missing layers"))?
+            .split_whitespace()
+            .map(|l| l.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid("layer sizes should be whole numbers"))?;
+        let weights = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|w| w.parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid("weights should be numbers"))?;
+
+        if layers.len() < 2 || weights.len() != weight_count(&layers) {
+            return Err(invalid("weights don't match layer sizes"));
+        }
+
+        Ok(Self { layers, weights })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::brain::Brain;
+    use bevy_rand::prelude::WyRand;
+    use rand::SeedableRng;
+
+    #[test]
+    fn predict_should_return_one_value_per_output() {
+        let mut rng = WyRand::seed_from_u64(42);
+        let brain = Brain::random(vec![4, 6, 2], &mut rng);
+
+        assert_eq!(brain.weights.len(), 5 * 6 + 7 * 2);
+
+        let outputs = brain.predict(&[0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs.iter().all(|o| (-1. ..=1.).contains(o)));
+    }
+
+    #[test]
+    fn predict_should_use_weights() {
+        // one input straight to one output, bias 0 and weight 1
+        let brain = Brain {
+            layers: vec![1, 1],
+            weights: vec![0., 1.],
+        };
+
+        assert_eq!(brain.predict(&[0.5]), vec![0.5_f32.tanh()]);
+    }
+
+    #[test]
+    fn should_save_and_load() {
+        let mut rng = WyRand::seed_from_u64(42);
+        let brain = Brain::random(vec![3, 4, 2], &mut rng);
+        let path = std::env::temp_dir().join("boids_brain_test.txt");
+
+        brain.save(&path).unwrap();
+        assert_eq!(Brain::load(&path).unwrap(), brain);
+
+        std::fs::write(&path, "3 4 2\n1 2 3\n").unwrap();
+        assert!(Brain::load(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
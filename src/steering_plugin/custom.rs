@@ -0,0 +1,196 @@
+// behaviours added from outside the crate, with
+// `app.register_steering_behaviour::<MyBehaviour>()`
+use bevy::prelude::*;
+
+use crate::input_plugin::MousePos;
+use crate::steering_plugin::behaviours::{VehicleQuery, VehicleQueryItem, VehicleSnapshot};
+use crate::steering_plugin::debug::{DebugCategory, DebugOverlay};
+use crate::steering_plugin::forces::SteeringQuery;
+use crate::steering_plugin::registry::BehaviourRegistry;
+use crate::steering_plugin::{Behaviour, Ship, SteeringSet, WorldBounds};
+
+/// What a custom behaviour knows about a ship when steering it. The built-in
+/// behaviours in `behaviours` can be used on `vehicle`
+#[derive(Clone, Copy, Debug)]
+pub struct ShipContext {
+    pub entity: Entity,
+    pub vehicle: VehicleSnapshot,
+    pub mouse: Vec2,
+    /// Seconds since the last tick
+    pub delta: f32,
+}
+
+/// A behaviour which can be added to the menu, shortcuts and command palette
+/// without changing this crate. It's kept as a resource, so can hold settings
+/// or state between ticks
+pub trait SteeringBehaviour: Resource + Default {
+    /// Shown on its button, and how it's found in the command palette.
+    /// Must be unique
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str = "";
+    /// Part of the debug overlay `draw_debug` belongs to, so its F key hides it
+    const DEBUG_CATEGORY: DebugCategory = DebugCategory::Perception;
+
+    /// Steering force for one ship this tick, added to anything else steering it.
+    /// It's limited to the ship's max force afterwards
+    fn steer(&mut self, ship: &ShipContext) -> Vec2;
+
+    /// When switching to this behaviour
+    fn setup(&mut self, _commands: &mut Commands) {}
+
+    /// When switching away, to despawn anything `setup` spawned
+    fn teardown(&mut self, _commands: &mut Commands) {}
+
+    /// Called for each ship while the overlay shows `DEBUG_CATEGORY`
+    fn draw_debug(&self, _ship: &ShipContext, _gizmos: &mut Gizmos) {}
+}
+
+pub trait SteeringBehaviourAppExt {
+    fn register_steering_behaviour<T: SteeringBehaviour>(&mut self) -> &mut Self;
+}
+
+impl SteeringBehaviourAppExt for App {
+    fn register_steering_behaviour<T: SteeringBehaviour>(&mut self) -> &mut Self {
+        let behaviour = Behaviour::Custom(T::NAME);
+
+        self.world_mut()
+            .get_resource_or_init::<BehaviourRegistry>()
+            .add(behaviour.clone(), T::NAME, T::DESCRIPTION);
+
+        self.init_resource::<T>()
+            .add_systems(
+                FixedUpdate,
+                custom_steering_system::<T>
+                    .in_set(SteeringSet::Behaviour)
+                    .run_if(in_state(behaviour.clone())),
+            )
+            .add_systems(
+                Update,
+                custom_debug_system::<T>.run_if(in_state(behaviour.clone())),
+            )
+            .add_systems(OnEnter(behaviour.clone()), custom_setup_system::<T>)
+            .add_systems(OnExit(behaviour), custom_teardown_system::<T>)
+    }
+}
+
+fn ship_context(
+    (entity, vehicle): (Entity, VehicleQueryItem),
+    bounds: &WorldBounds,
+    mouse: Vec2,
+    delta: f32,
+) -> ShipContext {
+    ShipContext {
+        entity,
+        vehicle: vehicle.snapshot(bounds),
+        mouse,
+        delta,
+    }
+}
+
+fn custom_steering_system<T: SteeringBehaviour>(
+    mut ships: Query<((Entity, VehicleQuery), SteeringQuery), With<Ship>>,
+    mut behaviour: ResMut<T>,
+    mouse_pos: Res<MousePos>,
+    time: Res<Time>,
+    bounds: Res<WorldBounds>,
+) {
+    for ((entity, vehicle), mut steering) in &mut ships {
+        if !vehicle.is_due() {
+            continue;
+        }
+        let ship = ship_context((entity, vehicle), &bounds, mouse_pos.0, time.delta_secs());
+        steering.add(T::NAME, behaviour.steer(&ship));
+    }
+}
+
+fn custom_debug_system<T: SteeringBehaviour>(
+    ships: Query<(Entity, VehicleQuery), With<Ship>>,
+    behaviour: Res<T>,
+    overlay: Res<DebugOverlay>,
+    mouse_pos: Res<MousePos>,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+    bounds: Res<WorldBounds>,
+) {
+    if !overlay.shows(T::DEBUG_CATEGORY) {
+        return;
+    }
+
+    for data in &ships {
+        let ship = ship_context(data, &bounds, mouse_pos.0, time.delta_secs());
+        behaviour.draw_debug(&ship, &mut gizmos);
+    }
+}
+
+fn custom_setup_system<T: SteeringBehaviour>(mut commands: Commands, mut behaviour: ResMut<T>) {
+    behaviour.setup(&mut commands);
+}
+
+fn custom_teardown_system<T: SteeringBehaviour>(mut commands: Commands, mut behaviour: ResMut<T>) {
+    behaviour.teardown(&mut commands);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game_plugin::{ShipAssets, ship_bundle};
+    use crate::headless_plugin::HeadlessPlugin;
+    use crate::input_plugin::InputPlugin;
+    use crate::steering_plugin::custom::{ShipContext, SteeringBehaviour, SteeringBehaviourAppExt};
+    use crate::steering_plugin::registry::BehaviourRegistry;
+    use crate::steering_plugin::{Behaviour, SteeringPlugin};
+    use avian2d::prelude::*;
+    use bevy::prelude::*;
+
+    #[derive(Resource, Default)]
+    struct Right {
+        ticks: u32,
+        setups: u32,
+    }
+
+    impl SteeringBehaviour for Right {
+        const NAME: &'static str = "Right";
+
+        fn steer(&mut self, _ship: &ShipContext) -> Vec2 {
+            self.ticks += 1;
+            Vec2::X
+        }
+
+        fn setup(&mut self, _commands: &mut Commands) {
+            self.setups += 1;
+        }
+    }
+
+    #[test]
+    fn registered_behaviour_should_run_when_selected() {
+        let mut app = App::new();
+        app.add_plugins((HeadlessPlugin, InputPlugin, SteeringPlugin))
+            .register_steering_behaviour::<Right>();
+        app.finish();
+        app.cleanup();
+
+        let registry = app.world().resource::<BehaviourRegistry>();
+        let entry = registry.0.last().unwrap();
+        assert_eq!(entry.name, "Right");
+        assert_eq!(entry.behaviour, Behaviour::Custom("Right"));
+
+        let assets = ShipAssets::placeholder();
+        let ship = app.world_mut().spawn(ship_bundle(&assets, Vec2::ZERO)).id();
+
+        app.update();
+        assert_eq!(app.world().resource::<Right>().setups, 0);
+
+        app.world_mut()
+            .resource_mut::<NextState<Behaviour>>()
+            .set(Behaviour::Custom("Right"));
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let right = app.world().resource::<Right>();
+        assert_eq!(right.setups, 1);
+        assert!(right.ticks > 0);
+        let velocity = app.world().get::<LinearVelocity>(ship).unwrap();
+        assert!(velocity.x > 0.);
+        assert_eq!(velocity.y, 0.);
+    }
+}
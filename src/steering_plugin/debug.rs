@@ -0,0 +1,145 @@
+// gizmos showing what the steering behaviours are thinking, for every ship
+use avian2d::prelude::*;
+use bevy::color::palettes::css::{AQUA, GRAY, LIME, ORANGE_RED};
+use bevy::prelude::*;
+
+use crate::steering_plugin::Ship;
+use crate::steering_plugin::forces::LastSteering;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DebugCategory {
+    Velocity,
+    Desired,
+    Steering,
+    Wander,
+    Prediction,
+    Path,
+    Perception,
+}
+
+impl DebugCategory {
+    /// In the order of their F1.. keys
+    pub const ALL: [DebugCategory; 7] = [
+        DebugCategory::Velocity,
+        DebugCategory::Desired,
+        DebugCategory::Steering,
+        DebugCategory::Wander,
+        DebugCategory::Prediction,
+        DebugCategory::Path,
+        DebugCategory::Perception,
+    ];
+}
+
+/// Nothing is drawn until `enabled`, then every category which hasn't been hidden
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    hidden: Vec<DebugCategory>,
+}
+
+impl DebugOverlay {
+    pub fn shows(&self, category: DebugCategory) -> bool {
+        self.enabled && !self.hidden.contains(&category)
+    }
+
+    pub fn toggle(&mut self, category: DebugCategory) {
+        if let Some(index) = self.hidden.iter().position(|c| *c == category) {
+            self.hidden.remove(index);
+        } else {
+            self.hidden.push(category);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DebugText;
+
+// vectors are in units per second, which would be far too long to draw as is
+const VELOCITY_SCALE: f32 = 0.3;
+const STEERING_SCALE: f32 = 0.1;
+
+/// Radius something is noticed within, plus a line to each neighbour noticed
+pub fn draw_perception(
+    gizmos: &mut Gizmos,
+    position: Vec2,
+    radius: f32,
+    neighbours: impl IntoIterator<Item = Vec2>,
+) {
+    gizmos.circle_2d(position, radius, GRAY);
+    for neighbour in neighbours {
+        gizmos.line_2d(position, neighbour, GRAY.with_alpha(0.4));
+    }
+}
+
+pub fn debug_overlay_system(
+    ships: Query<(&Position, &LinearVelocity, &LastSteering), With<Ship>>,
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+) {
+    if !overlay.enabled {
+        return;
+    }
+
+    for (position, velocity, last) in &ships {
+        if overlay.shows(DebugCategory::Velocity) {
+            gizmos.arrow_2d(position.0, position.0 + velocity.0 * VELOCITY_SCALE, LIME);
+        }
+        if overlay.shows(DebugCategory::Desired) {
+            gizmos.arrow_2d(
+                position.0,
+                position.0 + last.desired_velocity * VELOCITY_SCALE,
+                AQUA,
+            );
+        }
+        if overlay.shows(DebugCategory::Steering) {
+            gizmos.arrow_2d(
+                position.0,
+                position.0 + last.steering * STEERING_SCALE,
+                ORANGE_RED,
+            );
+        }
+    }
+}
+
+pub fn debug_text_system(mut text: Single<&mut Text, With<DebugText>>, overlay: Res<DebugOverlay>) {
+    if !overlay.enabled {
+        text.0 = String::new();
+        return;
+    }
+
+    let categories: Vec<String> = DebugCategory::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, category)| {
+            let state = if overlay.shows(*category) {
+                "on"
+            } else {
+                "off"
+            };
+            format!("F{} {:?}: {}", i + 1, category, state)
+        })
+        .collect();
+
+    text.0 = format!("Debug (D)\n{}", categories.join("\n"));
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::debug::{DebugCategory, DebugOverlay};
+
+    #[test]
+    fn overlay_should_hide_toggled_categories() {
+        let mut overlay = DebugOverlay::default();
+        assert!(!overlay.shows(DebugCategory::Wander));
+
+        overlay.enabled = true;
+        assert!(overlay.shows(DebugCategory::Wander));
+
+        overlay.toggle(DebugCategory::Wander);
+        assert!(!overlay.shows(DebugCategory::Wander));
+        assert!(overlay.shows(DebugCategory::Path));
+
+        overlay.toggle(DebugCategory::Wander);
+        assert!(overlay.shows(DebugCategory::Wander));
+    }
+}
@@ -0,0 +1,412 @@
+use avian2d::prelude::*;
+use bevy::color::palettes::css::{LIME, RED, YELLOW};
+use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+
+use crate::game_plugin::{SHIP_MAX_SPEED, ShipAssets, random_position, ship_bundle};
+use crate::steering_plugin::behaviours::{
+    Evade, Pursue, Steer, VehicleQuery, VehicleSnapshot, par_map,
+};
+use crate::steering_plugin::debug::{DebugCategory, DebugOverlay, draw_perception};
+use crate::steering_plugin::flock::{Align, Cohere, Neighbours, Separate};
+use crate::steering_plugin::forces::SteeringQuery;
+use crate::steering_plugin::{Ship, WorldBounds};
+
+#[derive(Component)]
+pub struct Predator;
+
+#[derive(Component)]
+pub struct Prey;
+
+/// Predators lose energy over time and starve at 0
+#[derive(Component)]
+pub struct Energy(pub f32);
+
+/// Ships spawned by the ecosystem, removed when leaving it
+#[derive(Component)]
+pub struct EcosystemShip;
+
+#[derive(Component)]
+pub struct PopulationText;
+
+#[derive(Resource)]
+pub struct EcosystemAssets {
+    predator: Handle<ColorMaterial>,
+    prey: Handle<ColorMaterial>,
+}
+
+#[derive(Resource)]
+pub struct ReproduceTimer(Timer);
+
+const PREDATOR_SPEED: f32 = 260.;
+const PREY_SPEED: f32 = 220.;
+const STARTING_ENERGY: f32 = 100.;
+const MAX_PREY: usize = 80;
+
+fn predator_bundle(assets: &EcosystemAssets) -> impl Bundle + use<> {
+    (
+        Predator,
+        Energy(STARTING_ENERGY),
+        MaxLinearSpeed(PREDATOR_SPEED),
+        MeshMaterial2d(assets.predator.clone()),
+        CollisionEventsEnabled,
+        EcosystemShip,
+    )
+}
+
+fn prey_bundle(assets: &EcosystemAssets) -> impl Bundle + use<> {
+    (
+        Prey,
+        MaxLinearSpeed(PREY_SPEED),
+        MeshMaterial2d(assets.prey.clone()),
+    )
+}
+
+pub fn on_start_ecosystem(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    ship_assets: Res<ShipAssets>,
+    bounds: Res<WorldBounds>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    let red: Color = RED.into();
+    let green: Color = LIME.into();
+    let assets = EcosystemAssets {
+        predator: materials.add(ColorMaterial::from(red)),
+        prey: materials.add(ColorMaterial::from(green)),
+    };
+
+    let predators = 3;
+    let prey = 30;
+
+    for i in 0..(predators + prey) {
+        let position = random_position(&mut *rng, &bounds);
+        let mut ship = commands.spawn((ship_bundle(&ship_assets, position), EcosystemShip));
+
+        if i < predators {
+            ship.insert(predator_bundle(&assets));
+        } else {
+            ship.insert(prey_bundle(&assets));
+        }
+    }
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        PopulationText,
+    ));
+
+    commands.insert_resource(assets);
+    commands.insert_resource(ReproduceTimer(Timer::from_seconds(
+        3.,
+        TimerMode::Repeating,
+    )));
+}
+
+// any other ships become prey
+#[allow(clippy::type_complexity)]
+pub fn join_ecosystem_system(
+    mut commands: Commands,
+    ships: Query<Entity, (With<Ship>, Without<Predator>, Without<Prey>)>,
+    assets: Res<EcosystemAssets>,
+) {
+    for ship in &ships {
+        commands.entity(ship).insert(prey_bundle(&assets));
+    }
+}
+
+// whichever prey is closest, if any are within `perception`
+fn nearest(vehicle: &VehicleSnapshot, prey: &Neighbours, perception: f32) -> Option<(Vec2, Vec2)> {
+    let distance = |prey_position: Vec2| vehicle.space.distance(vehicle.position, prey_position);
+
+    prey.positions()
+        .zip(prey.velocities())
+        .filter(|(prey_position, _)| distance(*prey_position) < perception)
+        .min_by(|(a, _), (b, _)| distance(*a).total_cmp(&distance(*b)))
+}
+
+#[allow(clippy::type_complexity)]
+pub fn predator_system(
+    mut predators: Query<(SteeringQuery, VehicleQuery), (With<Predator>, Without<Prey>)>,
+    prey: Query<(&Position, &LinearVelocity), (With<Prey>, Without<Predator>)>,
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    bounds: Res<WorldBounds>,
+) {
+    let perception = 300.;
+    let prey: Neighbours = prey
+        .iter()
+        .map(|(position, velocity)| (position.0, velocity.0))
+        .collect();
+
+    predators
+        .par_iter_mut()
+        .for_each(|(mut steering, vehicle)| {
+            if !vehicle.is_due() {
+                return;
+            }
+            let vehicle = vehicle.snapshot(&bounds);
+
+            // chase whichever is closest
+            if let Some((target_position, target_velocity)) = nearest(&vehicle, &prey, perception) {
+                let pursue = Pursue {
+                    target_position,
+                    target_velocity,
+                };
+                steering.add("pursue", pursue.steer(&vehicle));
+            }
+        });
+
+    if !overlay.shows(DebugCategory::Perception) && !overlay.shows(DebugCategory::Prediction) {
+        return;
+    }
+    for (_, vehicle) in &predators {
+        let vehicle = vehicle.snapshot(&bounds);
+        let chasing = nearest(&vehicle, &prey, perception);
+
+        if overlay.shows(DebugCategory::Perception) {
+            let chasing = chasing
+                .map(|(prey_position, _)| vehicle.space.nearest(vehicle.position, prey_position));
+            draw_perception(&mut gizmos, vehicle.position, perception, chasing);
+        }
+
+        if overlay.shows(DebugCategory::Prediction)
+            && let Some((target_position, target_velocity)) = chasing
+        {
+            let pursue = Pursue {
+                target_position,
+                target_velocity,
+            };
+            let prediction = pursue.prediction(&vehicle);
+            gizmos.line_2d(target_position, prediction, YELLOW);
+            gizmos.circle_2d(prediction, 5., YELLOW);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn prey_system(
+    mut prey: Query<(Entity, SteeringQuery, VehicleQuery), (With<Prey>, Without<Predator>)>,
+    predators: Query<(&Position, &LinearVelocity), (With<Predator>, Without<Prey>)>,
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    bounds: Res<WorldBounds>,
+) {
+    let flock_radius = 60.;
+    let panic_distance = 150.;
+
+    let flock: Neighbours = prey
+        .iter()
+        .map(|(_, _, vehicle)| (vehicle.position.0, vehicle.velocity.0))
+        .collect();
+    let predators: Vec<Evade> = predators
+        .iter()
+        .map(|(position, velocity)| Evade {
+            threat_position: position.0,
+            threat_velocity: velocity.0,
+        })
+        .collect();
+
+    let separate = Separate {
+        neighbours: &flock,
+        distance: 25.,
+    };
+    let align = Align {
+        neighbours: &flock,
+        radius: flock_radius,
+    };
+    let cohere = Cohere {
+        neighbours: &flock,
+        radius: flock_radius,
+    };
+
+    let (due, vehicles): (Vec<Entity>, Vec<VehicleSnapshot>) = prey
+        .iter()
+        .filter(|(.., vehicle)| vehicle.is_due())
+        .map(|(entity, _, vehicle)| (entity, vehicle.snapshot(&bounds)))
+        .unzip();
+
+    // the neighbour checks are most of the work, so they're done in batches
+    let flocking = par_map(&vehicles, |vehicle| {
+        let flock = [
+            ("separate", separate.steer(vehicle) * 1.5),
+            ("align", align.steer(vehicle)),
+            ("cohere", cohere.steer(vehicle)),
+        ];
+        // only from predators close enough to panic about
+        let evade: Vec<Vec2> = predators
+            .iter()
+            .filter(|evade| {
+                vehicle
+                    .space
+                    .distance(vehicle.position, evade.threat_position)
+                    < panic_distance
+            })
+            .map(|evade| evade.steer(vehicle) * 2.)
+            .collect();
+
+        (flock, evade)
+    });
+
+    for (entity, (flock, evade)) in due.into_iter().zip(flocking) {
+        let Ok((_, mut steering, _)) = prey.get_mut(entity) else {
+            continue;
+        };
+        for (behaviour, part) in flock {
+            steering.add(behaviour, part);
+        }
+        for part in evade {
+            steering.add("evade", part);
+        }
+    }
+
+    if overlay.shows(DebugCategory::Perception) {
+        for (.., vehicle) in &prey {
+            let vehicle = vehicle.snapshot(&bounds);
+            let neighbours = flock
+                .positions()
+                .map(|other| vehicle.space.nearest(vehicle.position, other))
+                .filter(|other| {
+                    let d = other.distance(vehicle.position);
+                    d > 0. && d < flock_radius
+                });
+            draw_perception(&mut gizmos, vehicle.position, flock_radius, neighbours);
+        }
+    }
+}
+
+// like reset_pursue_target, but the prey is eaten
+pub fn catch_system(
+    mut commands: Commands,
+    mut collision_event_reader: EventReader<CollisionStarted>,
+    mut predators: Query<&mut Energy, With<Predator>>,
+    prey: Query<(), With<Prey>>,
+) {
+    let meal = 60.;
+    let mut eaten = vec![];
+
+    for CollisionStarted(first, second) in collision_event_reader.read() {
+        let (predator, caught) = if prey.contains(*second) {
+            (*first, *second)
+        } else {
+            (*second, *first)
+        };
+
+        // may have already been caught by another predator this frame
+        if !prey.contains(caught) || eaten.contains(&caught) {
+            continue;
+        }
+        let Ok(mut energy) = predators.get_mut(predator) else {
+            continue;
+        };
+
+        energy.0 += meal;
+        commands.entity(caught).despawn();
+        eaten.push(caught);
+    }
+}
+
+// predators starve without food, and split in two when they've eaten enough
+pub fn energy_system(
+    mut commands: Commands,
+    mut predators: Query<(Entity, &mut Energy, &Position), With<Predator>>,
+    ship_assets: Res<ShipAssets>,
+    assets: Res<EcosystemAssets>,
+    time: Res<Time>,
+) {
+    let hunger = 8.;
+    let full = 200.;
+
+    for (entity, mut energy, position) in &mut predators {
+        energy.0 -= hunger * time.delta_secs();
+
+        if energy.0 <= 0. {
+            commands.entity(entity).despawn();
+        } else if energy.0 >= full {
+            energy.0 /= 2.;
+            commands
+                .spawn((ship_bundle(&ship_assets, position.0), EcosystemShip))
+                .insert(predator_bundle(&assets))
+                .insert(Energy(energy.0));
+        }
+    }
+}
+
+pub fn reproduce_prey_system(
+    mut commands: Commands,
+    prey: Query<&Position, With<Prey>>,
+    ship_assets: Res<ShipAssets>,
+    assets: Res<EcosystemAssets>,
+    mut timer: ResMut<ReproduceTimer>,
+    mut rng: GlobalEntropy<WyRand>,
+    time: Res<Time>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let chance = 0.2;
+    let mut population = prey.iter().len();
+
+    for position in &prey {
+        if population >= MAX_PREY {
+            break;
+        }
+
+        if rng.random_bool(chance) {
+            let offset = Vec2::new(rng.random_range(-20.0..20.), rng.random_range(-20.0..20.));
+            commands
+                .spawn((
+                    ship_bundle(&ship_assets, position.0 + offset),
+                    EcosystemShip,
+                ))
+                .insert(prey_bundle(&assets));
+            population += 1;
+        }
+    }
+}
+
+pub fn population_text_system(
+    mut text: Single<&mut Text, With<PopulationText>>,
+    predators: Query<(), With<Predator>>,
+    prey: Query<(), With<Prey>>,
+) {
+    text.0 = format!(
+        "Predators: {}\nPrey: {}",
+        predators.iter().len(),
+        prey.iter().len()
+    );
+}
+
+pub fn clean_up_ecosystem(
+    mut commands: Commands,
+    spawned: Query<Entity, With<EcosystemShip>>,
+    prey: Query<Entity, (With<Prey>, Without<EcosystemShip>)>,
+    text: Query<Entity, With<PopulationText>>,
+    ship_assets: Res<ShipAssets>,
+) {
+    for entity in spawned.iter().chain(text.iter()) {
+        commands.entity(entity).despawn();
+    }
+
+    // ships which were here before go back to normal
+    for entity in &prey {
+        commands.entity(entity).remove::<Prey>().insert((
+            MaxLinearSpeed(SHIP_MAX_SPEED),
+            MeshMaterial2d(ship_assets.material.clone()),
+        ));
+    }
+
+    commands.remove_resource::<EcosystemAssets>();
+    commands.remove_resource::<ReproduceTimer>();
+}
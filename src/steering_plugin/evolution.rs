@@ -0,0 +1,484 @@
+// https://natureofcode.com/genetic-algorithms/
+// evolutionary steering - ships learn to eat food and avoid poison
+use avian2d::prelude::*;
+use bevy::app::AppExit;
+use bevy::color::palettes::css::{LIME, RED};
+use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+
+use crate::game_plugin::{ShipAssets, random_position, ship_bundle};
+use crate::steering_plugin::WorldBounds;
+use crate::steering_plugin::behaviours::{Seek, Steer, VehicleQuery, VehicleSnapshot};
+use crate::steering_plugin::debug::{DebugCategory, DebugOverlay};
+use crate::steering_plugin::forces::SteeringQuery;
+
+const POPULATION: usize = 20;
+const FOOD: usize = 40;
+const POISON: usize = 15;
+// seconds of simulation per generation
+const GENERATION_LENGTH: f32 = 20.;
+const MUTATION_RATE: f32 = 0.1;
+
+/// Steering weights and parameters passed on to the next generation
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Genome {
+    /// negative weights steer away
+    pub food_weight: f32,
+    pub poison_weight: f32,
+    pub food_perception: f32,
+    pub poison_perception: f32,
+    pub max_speed: f32,
+}
+
+const WEIGHT_RANGE: (f32, f32) = (-2., 2.);
+const PERCEPTION_RANGE: (f32, f32) = (10., 200.);
+const SPEED_RANGE: (f32, f32) = (100., 300.);
+
+fn random_gene(range: (f32, f32), rng: &mut impl Rng) -> f32 {
+    rng.random_range(range.0..range.1)
+}
+
+fn mutate_gene(gene: &mut f32, range: (f32, f32), rate: f32, rng: &mut impl Rng) {
+    if rng.random_bool(rate as f64) {
+        // nudge by up to 10% of the range
+        let amount = (range.1 - range.0) * 0.1;
+        *gene = (*gene + rng.random_range(-amount..amount)).clamp(range.0, range.1);
+    }
+}
+
+impl Genome {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            food_weight: random_gene(WEIGHT_RANGE, rng),
+            poison_weight: random_gene(WEIGHT_RANGE, rng),
+            food_perception: random_gene(PERCEPTION_RANGE, rng),
+            poison_perception: random_gene(PERCEPTION_RANGE, rng),
+            max_speed: random_gene(SPEED_RANGE, rng),
+        }
+    }
+
+    /// each gene comes from one parent or the other
+    pub fn crossover(&self, other: &Genome, rng: &mut impl Rng) -> Self {
+        let mut pick = |a: f32, b: f32| if rng.random_bool(0.5) { a } else { b };
+
+        Self {
+            food_weight: pick(self.food_weight, other.food_weight),
+            poison_weight: pick(self.poison_weight, other.poison_weight),
+            food_perception: pick(self.food_perception, other.food_perception),
+            poison_perception: pick(self.poison_perception, other.poison_perception),
+            max_speed: pick(self.max_speed, other.max_speed),
+        }
+    }
+
+    /// `rate` is the chance of each gene changing
+    pub fn mutate(&mut self, rate: f32, rng: &mut impl Rng) {
+        mutate_gene(&mut self.food_weight, WEIGHT_RANGE, rate, rng);
+        mutate_gene(&mut self.poison_weight, WEIGHT_RANGE, rate, rng);
+        mutate_gene(&mut self.food_perception, PERCEPTION_RANGE, rate, rng);
+        mutate_gene(&mut self.poison_perception, PERCEPTION_RANGE, rate, rng);
+        mutate_gene(&mut self.max_speed, SPEED_RANGE, rate, rng);
+    }
+}
+
+/// Roulette wheel selection - fitter genomes are more likely to be picked.
+/// Returns an index into `fitness`
+pub fn select(fitness: &[f32], rng: &mut impl Rng) -> usize {
+    // everyone gets a small chance
+    let total: f32 = fitness.iter().map(|f| f.max(0.01)).sum();
+    let mut pick = rng.random_range(0.0..total);
+
+    for (i, f) in fitness.iter().enumerate() {
+        pick -= f.max(0.01);
+        if pick <= 0. {
+            return i;
+        }
+    }
+
+    fitness.len() - 1
+}
+
+/// Breeds a new population from the (genome, fitness) pairs of the last one
+pub fn next_generation(
+    previous: &[(Genome, f32)],
+    size: usize,
+    mutation_rate: f32,
+    rng: &mut impl Rng,
+) -> Vec<Genome> {
+    let fitness: Vec<f32> = previous.iter().map(|(_, f)| *f).collect();
+
+    (0..size)
+        .map(|_| {
+            let a = &previous[select(&fitness, rng)].0;
+            let b = &previous[select(&fitness, rng)].0;
+            let mut child = a.crossover(b, rng);
+            child.mutate(mutation_rate, rng);
+            child
+        })
+        .collect()
+}
+
+#[derive(Component)]
+pub struct Health(pub f32);
+
+#[derive(Component)]
+pub struct Fitness(pub f32);
+
+#[derive(Component)]
+pub struct EvolutionShip;
+
+#[derive(Component)]
+pub struct Food;
+
+#[derive(Component)]
+pub struct Poison;
+
+#[derive(Component)]
+pub struct EvolutionText;
+
+#[derive(Resource, Default)]
+pub struct Evolution {
+    pub generation: u32,
+    elapsed: f32,
+    pub best_fitness: f32,
+    // genomes and fitness of ships which have died this generation
+    graveyard: Vec<(Genome, f32)>,
+}
+
+/// Exit after this many generations, used when running headless
+#[derive(Resource)]
+pub struct GenerationLimit(pub u32);
+
+fn spawn_generation(
+    commands: &mut Commands,
+    ship_assets: &ShipAssets,
+    bounds: &WorldBounds,
+    genomes: Vec<Genome>,
+    rng: &mut impl Rng,
+) {
+    for genome in genomes {
+        let position = random_position(rng, bounds);
+        commands.spawn(ship_bundle(ship_assets, position)).insert((
+            MaxLinearSpeed(genome.max_speed),
+            genome,
+            Health(1.),
+            Fitness(0.),
+            EvolutionShip,
+        ));
+    }
+}
+
+pub fn on_start_evolution(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    ship_assets: Res<ShipAssets>,
+    bounds: Res<WorldBounds>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    let item_radius = 4.;
+    let circle = meshes.add(Circle::new(item_radius));
+    let green: Color = LIME.into();
+    let red: Color = RED.into();
+    let food_material = materials.add(ColorMaterial::from(green));
+    let poison_material = materials.add(ColorMaterial::from(red));
+
+    for i in 0..(FOOD + POISON) {
+        let position = random_position(&mut *rng, &bounds);
+        let mut item = commands.spawn(Transform::from_xyz(position.x, position.y, -1.));
+
+        if i < FOOD {
+            item.insert((
+                Mesh2d(circle.clone()),
+                MeshMaterial2d(food_material.clone()),
+                Food,
+            ));
+        } else {
+            item.insert((
+                Mesh2d(circle.clone()),
+                MeshMaterial2d(poison_material.clone()),
+                Poison,
+            ));
+        }
+    }
+
+    let genomes = (0..POPULATION).map(|_| Genome::random(&mut *rng)).collect();
+    spawn_generation(&mut commands, &ship_assets, &bounds, genomes, &mut *rng);
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        EvolutionText,
+    ));
+
+    commands.insert_resource(Evolution::default());
+}
+
+// the closest of `items` within `perception`, measured round wrapping edges
+fn nearest(vehicle: &VehicleSnapshot, items: &[Vec2], perception: f32) -> Option<Vec2> {
+    let distance = |item: Vec2| vehicle.space.distance(vehicle.position, item);
+
+    items
+        .iter()
+        .copied()
+        .filter(|item| distance(*item) < perception)
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+}
+
+#[allow(clippy::type_complexity)]
+pub fn evolution_steering_system(
+    mut ships: Query<(SteeringQuery, VehicleQuery, &Genome)>,
+    food: Query<&Transform, (With<Food>, Without<Poison>)>,
+    poison: Query<&Transform, (With<Poison>, Without<Food>)>,
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    bounds: Res<WorldBounds>,
+) {
+    let food: Vec<Vec2> = food.iter().map(|t| t.translation.truncate()).collect();
+    let poison: Vec<Vec2> = poison.iter().map(|t| t.translation.truncate()).collect();
+
+    ships
+        .par_iter_mut()
+        .for_each(|(mut steering, vehicle, genome)| {
+            if !vehicle.is_due() {
+                return;
+            }
+            let vehicle = vehicle.snapshot(&bounds);
+
+            if let Some(target) = nearest(&vehicle, &food, genome.food_perception) {
+                steering.add("food", Seek { target }.steer(&vehicle) * genome.food_weight);
+            }
+            if let Some(target) = nearest(&vehicle, &poison, genome.poison_perception) {
+                steering.add(
+                    "poison",
+                    Seek { target }.steer(&vehicle) * genome.poison_weight,
+                );
+            }
+        });
+
+    if overlay.shows(DebugCategory::Perception) {
+        for (_, vehicle, genome) in &ships {
+            gizmos.circle_2d(vehicle.position.0, genome.food_perception, LIME);
+            gizmos.circle_2d(vehicle.position.0, genome.poison_perception, RED);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn eat_system(
+    mut ships: Query<(&Position, &mut Health, &mut Fitness), With<Genome>>,
+    mut items: Query<(&mut Transform, Has<Food>), Or<(With<Food>, With<Poison>)>>,
+    bounds: Res<WorldBounds>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    let eat_distance = 12.;
+
+    for (position, mut health, mut fitness) in &mut ships {
+        for (mut transform, is_food) in &mut items {
+            if transform.translation.truncate().distance(position.0) > eat_distance {
+                continue;
+            }
+
+            if is_food {
+                health.0 = (health.0 + 0.3).min(1.5);
+                fitness.0 += 5.;
+            } else {
+                health.0 -= 0.5;
+                fitness.0 -= 5.;
+            }
+
+            // put it somewhere else
+            let new_position = random_position(&mut *rng, &bounds);
+            transform.translation = new_position.extend(-1.);
+        }
+    }
+}
+
+// ships lose health over time and die at 0, but get fitter the longer they live
+pub fn health_system(
+    mut commands: Commands,
+    mut ships: Query<(Entity, &Genome, &mut Health, &mut Fitness)>,
+    mut evolution: ResMut<Evolution>,
+    time: Res<Time>,
+) {
+    let hunger = 0.1;
+
+    for (entity, genome, mut health, mut fitness) in &mut ships {
+        health.0 -= hunger * time.delta_secs();
+        fitness.0 += time.delta_secs();
+
+        if health.0 <= 0. {
+            evolution.graveyard.push((genome.clone(), fitness.0));
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn generation_system(
+    mut commands: Commands,
+    ships: Query<(Entity, &Genome, &Fitness, &Health)>,
+    mut evolution: ResMut<Evolution>,
+    ship_assets: Res<ShipAssets>,
+    bounds: Res<WorldBounds>,
+    mut rng: GlobalEntropy<WyRand>,
+    time: Res<Time>,
+) {
+    evolution.elapsed += time.delta_secs();
+
+    let alive = ships
+        .iter()
+        .filter(|(_, _, _, health)| health.0 > 0.)
+        .count();
+    if evolution.elapsed < GENERATION_LENGTH && alive > 0 {
+        return;
+    }
+
+    let mut previous = std::mem::take(&mut evolution.graveyard);
+    for (entity, genome, fitness, health) in &ships {
+        // dead ones are already in the graveyard
+        if health.0 > 0. {
+            previous.push((genome.clone(), fitness.0));
+        }
+        commands.entity(entity).despawn();
+    }
+
+    if previous.is_empty() {
+        return;
+    }
+
+    let best = previous
+        .iter()
+        .map(|(_, fitness)| *fitness)
+        .fold(f32::MIN, f32::max);
+    let average = previous.iter().map(|(_, fitness)| fitness).sum::<f32>() / previous.len() as f32;
+    info!(
+        "Generation {}: best fitness {:.1}, average {:.1}",
+        evolution.generation, best, average
+    );
+
+    let genomes = next_generation(&previous, POPULATION, MUTATION_RATE, &mut *rng);
+    spawn_generation(&mut commands, &ship_assets, &bounds, genomes, &mut *rng);
+
+    evolution.generation += 1;
+    evolution.elapsed = 0.;
+    evolution.best_fitness = best;
+}
+
+pub fn evolution_text_system(
+    mut text: Single<&mut Text, With<EvolutionText>>,
+    evolution: Res<Evolution>,
+) {
+    text.0 = format!(
+        "Generation: {}\nBest fitness: {:.1}",
+        evolution.generation, evolution.best_fitness
+    );
+}
+
+pub fn generation_limit_system(
+    evolution: Res<Evolution>,
+    limit: Res<GenerationLimit>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if evolution.generation >= limit.0 {
+        exit.write(AppExit::Success);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn clean_up_evolution(
+    mut commands: Commands,
+    query: Query<
+        Entity,
+        Or<(
+            With<EvolutionShip>,
+            With<Food>,
+            With<Poison>,
+            With<EvolutionText>,
+        )>,
+    >,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+
+    commands.remove_resource::<Evolution>();
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::behaviours::{Space, VehicleSnapshot};
+    use crate::steering_plugin::evolution::{Genome, nearest, next_generation, select};
+    use bevy::prelude::*;
+    use bevy_rand::prelude::WyRand;
+    use rand::SeedableRng;
+
+    #[test]
+    fn select_should_prefer_fitter_genomes() {
+        let mut rng = WyRand::seed_from_u64(42);
+        let fitness = [1., 100., 1.];
+
+        let picks = (0..1000)
+            .filter(|_| select(&fitness, &mut rng) == 1)
+            .count();
+        assert!(picks > 900);
+    }
+
+    #[test]
+    fn nearest_should_see_food_across_wrapping_edge() {
+        let vehicle = VehicleSnapshot {
+            position: Vec2::new(390., 0.),
+            velocity: Vec2::ZERO,
+            max_speed: 100.,
+            max_force: 400.,
+            space: Space::Torus {
+                half_size: Vec2::new(400., 300.),
+            },
+        };
+        let food = [Vec2::new(300., 0.), Vec2::new(-390., 0.)];
+
+        assert_eq!(nearest(&vehicle, &food, 50.), Some(Vec2::new(-390., 0.)));
+    }
+
+    #[test]
+    fn crossover_should_only_use_parent_genes() {
+        let mut rng = WyRand::seed_from_u64(42);
+        let a = Genome::random(&mut rng);
+        let b = Genome::random(&mut rng);
+
+        let child = a.crossover(&b, &mut rng);
+        assert!(child.food_weight == a.food_weight || child.food_weight == b.food_weight);
+        assert!(child.max_speed == a.max_speed || child.max_speed == b.max_speed);
+    }
+
+    #[test]
+    fn mutate_should_stay_in_range() {
+        let mut rng = WyRand::seed_from_u64(42);
+        let mut genome = Genome::random(&mut rng);
+
+        for _ in 0..1000 {
+            genome.mutate(1., &mut rng);
+        }
+        assert!((-2. ..=2.).contains(&genome.food_weight));
+        assert!((10. ..=200.).contains(&genome.food_perception));
+        assert!((100. ..=300.).contains(&genome.max_speed));
+    }
+
+    #[test]
+    fn next_generation_should_keep_population_size() {
+        let mut rng = WyRand::seed_from_u64(42);
+        let previous: Vec<(Genome, f32)> = (0..5)
+            .map(|i| (Genome::random(&mut rng), i as f32))
+            .collect();
+
+        assert_eq!(next_generation(&previous, 20, 0.1, &mut rng).len(), 20);
+    }
+}
@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+
+use crate::math::set_magnitude;
+use crate::steering_plugin::behaviours::{Seek, Steer, VehicleSnapshot};
+
+/// Positions and velocities of the ships a behaviour reacts to, with each
+/// coordinate in its own array so the distance checks run over contiguous
+/// floats
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Neighbours {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub vx: Vec<f32>,
+    pub vy: Vec<f32>,
+}
+
+impl Neighbours {
+    pub fn push(&mut self, position: Vec2, velocity: Vec2) {
+        self.x.push(position.x);
+        self.y.push(position.y);
+        self.vx.push(velocity.x);
+        self.vy.push(velocity.y);
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Vec2> {
+        self.x.iter().zip(&self.y).map(|(x, y)| Vec2::new(*x, *y))
+    }
+
+    pub fn velocities(&self) -> impl Iterator<Item = Vec2> {
+        self.vx.iter().zip(&self.vy).map(|(x, y)| Vec2::new(*x, *y))
+    }
+}
+
+/// From (position, velocity) pairs
+impl FromIterator<(Vec2, Vec2)> for Neighbours {
+    fn from_iter<I: IntoIterator<Item = (Vec2, Vec2)>>(iter: I) -> Self {
+        let mut neighbours = Neighbours::default();
+        for (position, velocity) in iter {
+            neighbours.push(position, velocity);
+        }
+        neighbours
+    }
+}
+
+// https://natureofcode.com/autonomous-agents/#separation
+/// steer away from any neighbours closer than `distance`
+pub struct Separate<'a> {
+    pub neighbours: &'a Neighbours,
+    pub distance: f32,
+}
+
+impl Steer for Separate<'_> {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        let mut sum = Vec2::ZERO;
+        let mut count = 0;
+
+        for other in self.neighbours.positions() {
+            let away = -vehicle.space.delta(vehicle.position, other);
+            let d = away.length();
+            if d > 0. && d < self.distance {
+                // closer neighbours push harder
+                let away = away.normalize() / d;
+                sum += away;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return Vec2::ZERO;
+        }
+
+        sum /= count as f32;
+        set_magnitude(sum, vehicle.max_speed) - vehicle.velocity
+    }
+}
+
+// https://natureofcode.com/autonomous-agents/#flocking
+/// steer towards the average heading of neighbours within `radius`
+pub struct Align<'a> {
+    pub neighbours: &'a Neighbours,
+    pub radius: f32,
+}
+
+impl Steer for Align<'_> {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        let mut sum = Vec2::ZERO;
+        let mut count = 0;
+
+        let neighbours = self.neighbours;
+        for (other_position, other_velocity) in neighbours.positions().zip(neighbours.velocities())
+        {
+            let d = vehicle.space.distance(vehicle.position, other_position);
+            if d > 0. && d < self.radius {
+                sum += other_velocity;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return Vec2::ZERO;
+        }
+
+        set_magnitude(sum, vehicle.max_speed) - vehicle.velocity
+    }
+}
+
+/// steer towards the average position of neighbours within `radius`
+pub struct Cohere<'a> {
+    pub neighbours: &'a Neighbours,
+    pub radius: f32,
+}
+
+impl Steer for Cohere<'_> {
+    fn steer(&self, vehicle: &VehicleSnapshot) -> Vec2 {
+        let mut sum = Vec2::ZERO;
+        let mut count = 0;
+
+        // relative to the vehicle, so neighbours across a wrapping edge
+        // average out on the near side
+        for other in self.neighbours.positions() {
+            let offset = vehicle.space.delta(vehicle.position, other);
+            let d = offset.length();
+            if d > 0. && d < self.radius {
+                sum += offset;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return Vec2::ZERO;
+        }
+
+        Seek {
+            target: vehicle.position + sum / count as f32,
+        }
+        .steer(vehicle)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::behaviours::{Space, Steer, VehicleSnapshot};
+    use crate::steering_plugin::flock::{Align, Cohere, Neighbours, Separate};
+    use bevy::prelude::*;
+    use proptest::prelude::*;
+
+    fn vehicle(velocity: Vec2) -> VehicleSnapshot {
+        VehicleSnapshot {
+            position: Vec2::ZERO,
+            velocity,
+            max_speed: 10.,
+            max_force: 10.,
+            space: Space::Plane,
+        }
+    }
+
+    // standing still
+    fn at(positions: &[Vec2]) -> Neighbours {
+        positions
+            .iter()
+            .map(|position| (*position, Vec2::ZERO))
+            .collect()
+    }
+
+    #[test]
+    fn separate_should_ignore_distant_neighbours() {
+        let neighbours = at(&[Vec2::new(100., 0.), Vec2::new(0., -100.)]);
+        let separate = Separate {
+            neighbours: &neighbours,
+            distance: 25.,
+        };
+
+        assert_eq!(separate.steer(&vehicle(Vec2::new(1., 1.))), Vec2::ZERO);
+    }
+
+    #[test]
+    fn separate_should_steer_away_from_close_neighbours() {
+        let neighbours = at(&[Vec2::new(10., 0.), Vec2::ZERO]);
+        let separate = Separate {
+            neighbours: &neighbours,
+            distance: 25.,
+        };
+
+        // ignores itself at the same position
+        assert_eq!(separate.steer(&vehicle(Vec2::ZERO)), Vec2::new(-10., 0.));
+    }
+
+    #[test]
+    fn align_should_match_neighbour_heading() {
+        let neighbours = Neighbours::from_iter([
+            (Vec2::new(10., 0.), Vec2::new(0., 5.)),
+            (Vec2::new(-10., 0.), Vec2::new(0., 15.)),
+            // too far away to count
+            (Vec2::new(500., 0.), Vec2::new(100., 0.)),
+        ]);
+        let align = Align {
+            neighbours: &neighbours,
+            radius: 50.,
+        };
+
+        assert_eq!(align.steer(&vehicle(Vec2::ZERO)), Vec2::new(0., 10.));
+    }
+
+    #[test]
+    fn cohere_should_steer_to_centre_of_neighbours() {
+        let neighbours = at(&[Vec2::new(10., 10.), Vec2::new(10., -10.)]);
+        let cohere = Cohere {
+            neighbours: &neighbours,
+            radius: 50.,
+        };
+
+        assert_eq!(cohere.steer(&vehicle(Vec2::ZERO)), Vec2::new(10., 0.));
+    }
+
+    #[test]
+    fn flocking_should_see_neighbours_across_wrapping_edges() {
+        let ship = VehicleSnapshot {
+            position: Vec2::new(395., 0.),
+            space: Space::Torus {
+                half_size: Vec2::new(400., 300.),
+            },
+            ..vehicle(Vec2::ZERO)
+        };
+        // 10 to the right, round the edge
+        let neighbours = at(&[Vec2::new(-395., 0.)]);
+
+        let separate = Separate {
+            neighbours: &neighbours,
+            distance: 20.,
+        };
+        assert_eq!(separate.steer(&ship), Vec2::new(-10., 0.));
+
+        let cohere = Cohere {
+            neighbours: &neighbours,
+            radius: 20.,
+        };
+        assert_eq!(cohere.steer(&ship), Vec2::new(10., 0.));
+    }
+
+    fn neighbours() -> impl Strategy<Value = Neighbours> {
+        prop::collection::vec((-100f32..100., -100f32..100.), 0..20).prop_map(|points| {
+            points
+                .into_iter()
+                .map(|(x, y)| (Vec2::new(x, y), Vec2::new(-y, x)))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn neighbours_should_keep_coordinates_in_order() {
+        let neighbours = Neighbours::from_iter([
+            (Vec2::new(1., 2.), Vec2::new(3., 4.)),
+            (Vec2::new(5., 6.), Vec2::new(7., 8.)),
+        ]);
+
+        assert_eq!(neighbours.x, [1., 5.]);
+        assert_eq!(neighbours.vy, [4., 8.]);
+        assert_eq!(neighbours.len(), 2);
+        assert_eq!(
+            neighbours.positions().collect::<Vec<_>>(),
+            [Vec2::new(1., 2.), Vec2::new(5., 6.)]
+        );
+        assert_eq!(
+            neighbours.velocities().collect::<Vec<_>>(),
+            [Vec2::new(3., 4.), Vec2::new(7., 8.)]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn flocking_should_stay_within_max_speed(neighbours in neighbours(), vx in -10f32..10., vy in -10f32..10.) {
+            let ship = vehicle(Vec2::new(vx, vy));
+
+            let steerings = [
+                Separate { neighbours: &neighbours, distance: 25. }.steer(&ship),
+                Align { neighbours: &neighbours, radius: 50. }.steer(&ship),
+                Cohere { neighbours: &neighbours, radius: 50. }.steer(&ship),
+            ];
+
+            for steering in steerings {
+                prop_assert!(steering.is_finite());
+                // either no change or a new velocity no faster than max speed
+                prop_assert!(steering == Vec2::ZERO || (ship.velocity + steering).length() <= 10.001);
+            }
+        }
+
+        #[test]
+        fn separate_should_push_away_from_single_neighbour(x in -20f32..20., y in -20f32..20.) {
+            let neighbour = Vec2::new(x, y);
+            prop_assume!(neighbour.length() > 0.01 && neighbour.length() < 25.);
+            let neighbours = at(&[neighbour]);
+            let separate = Separate { neighbours: &neighbours, distance: 25. };
+
+            prop_assert!(separate.steer(&vehicle(Vec2::ZERO)).dot(neighbour) < 0.);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// behaviours add to a ship's Steering instead of changing its velocity directly,
+// then it's applied once per frame either as a change in velocity or as a force
+use avian2d::prelude::*;
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+
+use crate::math::limit;
+use crate::steering_plugin::lod::{LodState, is_due};
+use crate::steering_plugin::{Behaviour, Ship};
+
+/// Acceleration asked for by behaviours this frame, cleared once applied
+#[derive(Component, Default)]
+pub struct Steering(pub Vec2);
+
+/// Each behaviour's part of the `Steering`, by name, before it's limited. Only
+/// recorded for ships which have this, i.e. the one being inspected
+#[derive(Component, Default)]
+pub struct SteeringParts(pub Vec<(&'static str, Vec2)>);
+
+/// A ship's `Steering`, for behaviours to add to
+#[derive(QueryData)]
+#[query_data(mutable)]
+pub struct SteeringQuery {
+    pub steering: &'static mut Steering,
+    pub parts: Option<&'static mut SteeringParts>,
+}
+
+impl SteeringQueryItem<'_> {
+    /// Adds `behaviour`'s steering, noting it down if the ship keeps `SteeringParts`
+    pub fn add(&mut self, behaviour: &'static str, steering: Vec2) {
+        self.steering.0 += steering;
+        if let Some(parts) = &mut self.parts {
+            parts.0.push((behaviour, steering));
+        }
+    }
+}
+
+/// What the behaviours asked for last, kept for the debug overlay
+#[derive(Component, Default)]
+pub struct LastSteering {
+    pub desired_velocity: Vec2,
+    pub steering: Vec2,
+    /// change avoidance made to the steering, if it's on
+    pub avoidance: Vec2,
+}
+
+/// Strongest force a ship can steer with, so how agile it is. Heavier ships
+/// accelerate less with the same force
+#[derive(Component)]
+pub struct MaxSteeringForce(pub f32);
+
+/// When on, ships are dynamic bodies pushed by forces, so they bump into each
+/// other and walls. Otherwise they're kinematic and steering sets velocity
+#[derive(Resource)]
+pub struct DynamicShips(pub bool);
+
+// https://natureofcode.com/autonomous-agents/#vehicles-and-steering
+/// Largest acceleration `max_force` can give `mass` in the direction of `steering`
+pub fn limit_steering(steering: Vec2, mass: f32, max_force: f32) -> Vec2 {
+    limit(steering, max_force / mass)
+}
+
+// before the behaviours. Ships skipping this tick keep the parts of the steering
+// they're holding on to
+pub fn clear_steering_parts_system(mut query: Query<(&mut SteeringParts, Option<&LodState>)>) {
+    for (mut parts, lod) in &mut query {
+        if is_due(lod) {
+            parts.0.clear();
+        }
+    }
+}
+
+// runs on the combined output of the behaviours, before avoidance has its say
+pub fn limit_steering_system(
+    mut query: Query<(
+        &mut Steering,
+        &mut LastSteering,
+        &LinearVelocity,
+        &ComputedMass,
+        &MaxSteeringForce,
+    )>,
+) {
+    query
+        .par_iter_mut()
+        .for_each(|(mut steering, mut last, velocity, mass, max_force)| {
+            // steering is desired velocity - velocity, before it's limited
+            last.desired_velocity = velocity.0 + steering.0;
+
+            steering.0 = limit_steering(steering.0, mass.value(), max_force.0);
+            last.steering = steering.0;
+            last.avoidance = Vec2::ZERO;
+        });
+}
+
+pub fn apply_steering_system(
+    mut query: Query<(
+        &mut Steering,
+        &mut LinearVelocity,
+        &mut ExternalForce,
+        &RigidBody,
+        &ComputedMass,
+    )>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+
+    query.par_iter_mut().for_each(
+        |(mut steering, mut velocity, mut force, rigid_body, mass)| {
+            if rigid_body.is_dynamic() {
+                // left set until next frame, as physics may step more than once per frame
+                force.set_force(steering.0 * mass.value());
+            } else {
+                velocity.0 += steering.0 * delta;
+            }
+
+            steering.0 = Vec2::ZERO;
+        },
+    );
+}
+
+// also catches ships spawned since the last switch
+pub fn sync_rigid_body_system(
+    mut ships: Query<(&mut RigidBody, &mut ExternalForce), With<Ship>>,
+    dynamic: Res<DynamicShips>,
+    behaviour: Res<State<Behaviour>>,
+) {
+    // kinematic ships would pass straight through the queue's walls
+    let wanted = if dynamic.0 || *behaviour == Behaviour::Queue {
+        RigidBody::Dynamic
+    } else {
+        RigidBody::Kinematic
+    };
+
+    for (mut rigid_body, mut force) in &mut ships {
+        if *rigid_body != wanted {
+            *rigid_body = wanted;
+            force.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::forces::limit_steering;
+    use bevy::prelude::*;
+
+    #[test]
+    fn limit_steering_should_respect_mass_and_limit() {
+        assert_eq!(
+            limit_steering(Vec2::new(10., 0.), 2., 100.),
+            Vec2::new(10., 0.)
+        );
+
+        // heavier ships need more force, so hit the limit sooner
+        assert_eq!(
+            limit_steering(Vec2::new(0., 10.), 20., 100.),
+            Vec2::new(0., 5.)
+        );
+    }
+}
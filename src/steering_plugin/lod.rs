@@ -0,0 +1,198 @@
+// level of detail: ships far from the focus, or off screen, only work out their
+// steering every few ticks. In between they keep the force they last worked out,
+// and physics still moves them every tick
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::input_plugin::MainCamera;
+use crate::steering_plugin::forces::Steering;
+
+/// Where distances for the LOD tiers are measured from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LodFocus {
+    /// Centre of the `MainCamera`, and ships outside its view count as off screen
+    Camera,
+    Point(Vec2),
+}
+
+/// Ships at least `distance` from the focus steer every `interval` ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodTier {
+    pub distance: f32,
+    pub interval: u32,
+}
+
+/// Toggled with `L`. Off by default, so every ship steers every tick
+#[derive(Resource, Clone, Debug)]
+pub struct SteeringLod {
+    pub enabled: bool,
+    pub focus: LodFocus,
+    /// Nearest first. Ships closer than the first tier steer every tick
+    pub tiers: Vec<LodTier>,
+    /// Ticks between steering for ships the camera can't see, if it's the focus
+    pub off_screen_interval: u32,
+}
+
+impl Default for SteeringLod {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            focus: LodFocus::Camera,
+            tiers: vec![
+                LodTier {
+                    distance: 300.,
+                    interval: 2,
+                },
+                LodTier {
+                    distance: 600.,
+                    interval: 4,
+                },
+            ],
+            off_screen_interval: 8,
+        }
+    }
+}
+
+impl SteeringLod {
+    /// Ticks between steering for a ship `distance` from the focus
+    pub fn interval(&self, distance: f32, on_screen: bool) -> u32 {
+        if !self.enabled {
+            return 1;
+        }
+
+        let interval = self
+            .tiers
+            .iter()
+            .rev()
+            .find(|tier| distance >= tier.distance)
+            .map_or(1, |tier| tier.interval);
+
+        if on_screen {
+            interval.max(1)
+        } else {
+            interval.max(self.off_screen_interval).max(1)
+        }
+    }
+}
+
+/// Whether a ship steers this tick, set by `lod_system`
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LodState {
+    pub interval: u32,
+    pub due: bool,
+    /// steering from the last tick it was due
+    held: Vec2,
+}
+
+impl Default for LodState {
+    fn default() -> Self {
+        Self {
+            interval: 1,
+            due: true,
+            held: Vec2::ZERO,
+        }
+    }
+}
+
+/// Ships without a `LodState` steer every tick
+pub fn is_due(lod: Option<&LodState>) -> bool {
+    lod.is_none_or(|lod| lod.due)
+}
+
+// before the behaviours. Ships are spread over the ticks by entity, so only
+// about 1 / interval of each tier steers in any one tick
+pub fn lod_system(
+    mut ships: Query<(Entity, &Position, &mut LodState)>,
+    lod: Res<SteeringLod>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut tick: Local<u32>,
+) {
+    *tick = tick.wrapping_add(1);
+    let tick = *tick;
+
+    let camera = match lod.focus {
+        LodFocus::Camera => camera.single().ok(),
+        LodFocus::Point(_) => None,
+    };
+    let focus = match (lod.focus, camera) {
+        (LodFocus::Point(point), _) => point,
+        (LodFocus::Camera, Some((_, transform))) => transform.translation().truncate(),
+        // headless
+        (LodFocus::Camera, None) => Vec2::ZERO,
+    };
+
+    ships
+        .par_iter_mut()
+        .for_each(|(entity, position, mut state)| {
+            let on_screen = camera.is_none_or(|(camera, transform)| {
+                camera
+                    .world_to_ndc(transform, position.0.extend(0.))
+                    .is_some_and(|ndc| ndc.x.abs() <= 1. && ndc.y.abs() <= 1.)
+            });
+
+            state.interval = lod.interval(position.distance(focus), on_screen);
+            state.due = tick.wrapping_add(entity.index()) % state.interval == 0;
+        });
+}
+
+// after the behaviours, so ships which skipped them keep steering the same way
+pub fn hold_steering_system(mut ships: Query<(&mut Steering, &mut LodState)>) {
+    ships.par_iter_mut().for_each(|(mut steering, mut state)| {
+        if state.due {
+            state.held = steering.0;
+        } else {
+            steering.0 = state.held;
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::lod::{LodFocus, LodTier, SteeringLod};
+    use bevy::prelude::*;
+
+    fn lod() -> SteeringLod {
+        SteeringLod {
+            enabled: true,
+            focus: LodFocus::Point(Vec2::ZERO),
+            tiers: vec![
+                LodTier {
+                    distance: 100.,
+                    interval: 2,
+                },
+                LodTier {
+                    distance: 200.,
+                    interval: 5,
+                },
+            ],
+            off_screen_interval: 8,
+        }
+    }
+
+    #[test]
+    fn interval_should_come_from_furthest_tier_reached() {
+        let lod = lod();
+
+        assert_eq!(lod.interval(50., true), 1);
+        assert_eq!(lod.interval(100., true), 2);
+        assert_eq!(lod.interval(199., true), 2);
+        assert_eq!(lod.interval(1000., true), 5);
+    }
+
+    #[test]
+    fn off_screen_ships_should_steer_least_often() {
+        let mut lod = lod();
+
+        assert_eq!(lod.interval(50., false), 8);
+        lod.off_screen_interval = 3;
+        assert_eq!(lod.interval(1000., false), 5);
+    }
+
+    #[test]
+    fn disabled_lod_should_steer_every_tick() {
+        let mut lod = lod();
+        lod.enabled = false;
+
+        assert_eq!(lod.interval(1000., false), 1);
+    }
+}
@@ -1,27 +1,77 @@
 use avian2d::prelude::*;
-use bevy::prelude::ops::{cos, sin};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_rand::prelude::*;
+use clap::ValueEnum;
 use rand::Rng;
-use std::f32::consts::PI;
+use std::path::PathBuf;
 
 use bevy::color::palettes::css::{YELLOW, YELLOW_GREEN};
 
+use crate::input_plugin::MousePos;
+use crate::math::{heading, heading_to_rotation};
+use crate::steering_plugin::behaviours::{Seek, Steer, VehicleQuery, Wander};
 use crate::steering_plugin::path_follow::clean_up_path;
-use crate::{
-    input_plugin::MousePos,
-    utils::{adjust_magnitude, heading, set_magnitude},
-};
 
+pub mod arrive;
+pub mod behaviours;
+mod brain;
+pub mod custom;
+pub mod debug;
+mod ecosystem;
 mod evade;
-mod path_follow;
+pub mod evolution;
+pub mod flock;
+pub mod forces;
+pub mod lod;
+pub mod neuroevolution;
+pub mod orca;
+pub mod path_follow;
 mod pursue;
-
+mod queue;
+pub mod registry;
+pub mod svg;
+pub mod trails;
+pub mod vehicle;
+
+use arrive::{ArriveSettings, Arrived, arrive_system, arrived_system, clean_up_arrive};
+use debug::{DebugCategory, DebugOverlay, DebugText, debug_overlay_system, debug_text_system};
+use ecosystem::{
+    catch_system, clean_up_ecosystem, energy_system, join_ecosystem_system, on_start_ecosystem,
+    population_text_system, predator_system, prey_system, reproduce_prey_system,
+};
 use evade::{evade_system, flee_system};
+use evolution::{
+    GenerationLimit, clean_up_evolution, eat_system, evolution_steering_system,
+    evolution_text_system, generation_limit_system, generation_system, health_system,
+    on_start_evolution,
+};
+use forces::{
+    DynamicShips, SteeringQuery, apply_steering_system, clear_steering_parts_system,
+    limit_steering_system, sync_rigid_body_system,
+};
+use lod::{SteeringLod, hold_steering_system, lod_system};
+use neuroevolution::{
+    BrainFile, BrainTarget, brain_steering_system, brain_target_system, clean_up_brain,
+    clean_up_neuroevolution, crash_system, neuro_generation_limit_system, neuro_generation_system,
+    neuro_text_system, on_start_brain, on_start_neuroevolution, training_target_system,
+};
+use orca::{LocalAvoidance, orca_system, sync_obstacles_system};
 use path_follow::{Path, on_start_path, path_follow_system};
 use pursue::{clean_up_pursue, on_start_pursue, pursue_system, reset_pursue_target};
+use queue::{
+    QueueProgress, clean_up_queue, join_queue_system, on_start_queue, queue_system,
+    rejoin_queue_system,
+};
+use registry::BehaviourRegistry;
+use svg::{SvgExport, export_svg_on_exit_system};
+use trails::{
+    Heatmap, HeatmapExport, ShowHeatmap, ShowTrails, clear_heatmap_system, draw_heatmap_system,
+    draw_trails_system, export_heatmap_on_exit_system, record_heatmap_system, record_trails_system,
+};
+use vehicle::{Vehicle, VehicleModel, sync_vehicles_system, vehicle_system};
 
-#[derive(PartialEq, Debug, Hash, Eq, Clone, States, Default, Component)]
+#[derive(PartialEq, Debug, Hash, Eq, Clone, States, Default, Component, ValueEnum)]
 pub enum Behaviour {
     #[default]
     Seek,
@@ -31,25 +81,67 @@ pub enum Behaviour {
     Flee,
     Evade,
     PathFollow,
+    Queue,
+    Ecosystem,
+    Evolve,
+    NeuroEvolve,
+    Brain,
+    /// Added with `register_steering_behaviour`, by its name
+    #[value(skip)]
+    Custom(&'static str),
 }
 
-#[derive(Resource)]
-struct Debug(bool);
+/// Order of steering within a frame - behaviours add to each ship's `Steering`,
+/// it's limited to the ship's `MaxSteeringForce`, avoidance adjusts it, then
+/// it's applied to the ship
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SteeringSet {
+    Behaviour,
+    Limit,
+    Avoidance,
+    Apply,
+}
 
 pub struct SteeringPlugin;
 
 impl Plugin for SteeringPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(EntropyPlugin::<WyRand>::default())
-            .insert_resource(Debug(false))
-            .insert_resource(Theta(PI / 2.))
+            .init_resource::<DebugOverlay>()
+            .insert_resource(LocalAvoidance(false))
+            .insert_resource(DynamicShips(false))
+            .insert_resource(VehicleModel(None))
+            .init_resource::<SteeringLod>()
+            .init_resource::<ArriveSettings>()
+            .add_event::<Arrived>()
+            .insert_resource(Gravity::ZERO)
+            .init_resource::<BrainFile>()
+            .init_resource::<OutputDir>()
+            .init_resource::<BehaviourRegistry>()
+            .insert_resource(WorldBounds {
+                half_size: Vec2::new(400., 300.),
+            })
             .insert_resource(Path {
                 points: vec![],
                 radius: 10.,
             })
+            .insert_resource(ShowTrails(false))
+            .insert_resource(ShowHeatmap(false))
+            .init_resource::<Heatmap>()
             .add_systems(Startup, setup)
+            .configure_sets(
+                FixedUpdate,
+                (
+                    SteeringSet::Behaviour,
+                    SteeringSet::Limit,
+                    SteeringSet::Avoidance,
+                    SteeringSet::Apply,
+                )
+                    .chain(),
+            )
+            // steering runs in step with physics, so it pauses and slows down with it
             .add_systems(
-                Update,
+                FixedUpdate,
                 (
                     seek_system.run_if(in_state(Behaviour::Seek)),
                     arrive_system.run_if(in_state(Behaviour::Arrive)),
@@ -58,206 +150,280 @@ impl Plugin for SteeringPlugin {
                     flee_system.run_if(in_state(Behaviour::Flee)),
                     evade_system.run_if(in_state(Behaviour::Evade)),
                     path_follow_system.run_if(in_state(Behaviour::PathFollow)),
+                    (join_queue_system, queue_system, rejoin_queue_system)
+                        .chain()
+                        .run_if(in_state(Behaviour::Queue)),
+                    (join_ecosystem_system, predator_system, prey_system)
+                        .chain()
+                        .run_if(in_state(Behaviour::Ecosystem)),
+                    evolution_steering_system.run_if(in_state(Behaviour::Evolve)),
+                    brain_steering_system
+                        .run_if(in_state(Behaviour::NeuroEvolve).or(in_state(Behaviour::Brain))),
+                )
+                    .in_set(SteeringSet::Behaviour),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    orca_system
+                        // steers queueing ships round the walls rather than into them
+                        .run_if(
+                            (|avoidance: Res<LocalAvoidance>| avoidance.0)
+                                .or(in_state(Behaviour::Queue)),
+                        )
+                        .in_set(SteeringSet::Avoidance),
+                    sync_obstacles_system.run_if(resource_changed::<LocalAvoidance>),
+                    lod_system.before(SteeringSet::Behaviour),
+                    clear_steering_parts_system
+                        .after(lod_system)
+                        .before(SteeringSet::Behaviour),
+                    wrap_edges_system.before(SteeringSet::Behaviour),
+                    (hold_steering_system, limit_steering_system)
+                        .chain()
+                        .in_set(SteeringSet::Limit),
+                    vehicle_system
+                        .in_set(SteeringSet::Apply)
+                        .before(apply_steering_system),
+                    apply_steering_system.in_set(SteeringSet::Apply),
+                    sync_vehicles_system,
+                    sync_rigid_body_system.before(SteeringSet::Apply),
                     reset_pursue_target,
-                    rotate_system,
+                    arrived_system
+                        .after(SteeringSet::Apply)
+                        .run_if(in_state(Behaviour::Arrive)),
+                    (catch_system, energy_system, reproduce_prey_system)
+                        .run_if(in_state(Behaviour::Ecosystem)),
+                    (
+                        eat_system,
+                        health_system,
+                        generation_system,
+                        generation_limit_system.run_if(resource_exists::<GenerationLimit>),
+                    )
+                        .chain()
+                        .run_if(in_state(Behaviour::Evolve)),
+                    (
+                        crash_system,
+                        training_target_system,
+                        neuro_generation_system,
+                        neuro_generation_limit_system.run_if(resource_exists::<GenerationLimit>),
+                    )
+                        .chain()
+                        .run_if(in_state(Behaviour::NeuroEvolve)),
+                    brain_target_system
+                        .before(SteeringSet::Behaviour)
+                        .run_if(in_state(Behaviour::Brain)),
+                    rotate_system.after(SteeringSet::Apply),
+                    (record_trails_system, record_heatmap_system).after(SteeringSet::Apply),
                 ),
             )
+            .add_systems(
+                Update,
+                (
+                    population_text_system.run_if(in_state(Behaviour::Ecosystem)),
+                    evolution_text_system.run_if(in_state(Behaviour::Evolve)),
+                    neuro_text_system.run_if(in_state(Behaviour::NeuroEvolve)),
+                    debug_overlay_system,
+                    debug_text_system,
+                    clear_heatmap_system.run_if(state_changed::<Behaviour>),
+                    draw_trails_system,
+                    draw_heatmap_system,
+                ),
+            )
+            .add_systems(
+                Last,
+                (
+                    export_heatmap_on_exit_system.run_if(resource_exists::<HeatmapExport>),
+                    export_svg_on_exit_system.run_if(resource_exists::<SvgExport>),
+                ),
+            )
+            .add_systems(OnExit(Behaviour::Arrive), clean_up_arrive)
             .add_systems(OnEnter(Behaviour::Pursue), on_start_pursue)
             .add_systems(OnExit(Behaviour::Pursue), clean_up_pursue)
             .add_systems(OnEnter(Behaviour::Evade), on_start_pursue)
             .add_systems(OnExit(Behaviour::Evade), clean_up_pursue)
             .add_systems(OnEnter(Behaviour::PathFollow), on_start_path)
-            .add_systems(OnExit(Behaviour::PathFollow), clean_up_path);
+            .add_systems(OnExit(Behaviour::PathFollow), clean_up_path)
+            .add_systems(OnEnter(Behaviour::Queue), on_start_queue)
+            .add_systems(OnExit(Behaviour::Queue), clean_up_queue)
+            .add_systems(OnEnter(Behaviour::Ecosystem), on_start_ecosystem)
+            .add_systems(OnExit(Behaviour::Ecosystem), clean_up_ecosystem)
+            .add_systems(OnEnter(Behaviour::Evolve), on_start_evolution)
+            .add_systems(OnExit(Behaviour::Evolve), clean_up_evolution)
+            .add_systems(OnEnter(Behaviour::NeuroEvolve), on_start_neuroevolution)
+            .add_systems(OnExit(Behaviour::NeuroEvolve), clean_up_neuroevolution)
+            .add_systems(OnEnter(Behaviour::Brain), on_start_brain)
+            .add_systems(OnExit(Behaviour::Brain), clean_up_brain);
     }
 }
 
+/// Area ships are kept inside, centred on 0,0
 #[derive(Resource)]
-struct Theta(f32);
+pub struct WorldBounds {
+    pub half_size: Vec2,
+}
 
-#[derive(Component)]
-pub struct Ship;
+/// Directory heatmaps and scenes are saved to
+#[derive(Resource)]
+pub struct OutputDir(pub PathBuf);
 
-#[derive(Component)]
-pub struct WanderTarget;
+impl Default for OutputDir {
+    fn default() -> Self {
+        Self(PathBuf::from("."))
+    }
+}
+
+/// Where ships are heading in the current behaviour, if it has a target
+#[derive(SystemParam)]
+pub struct SteeringTargets<'w, 's> {
+    behaviour: Res<'w, State<Behaviour>>,
+    mouse_pos: Res<'w, MousePos>,
+    pursue_target: Query<'w, 's, &'static Position, With<PursueTarget>>,
+    queue_progress: Query<'w, 's, &'static QueueProgress>,
+    brain_targets: Query<'w, 's, &'static BrainTarget>,
+}
+
+impl SteeringTargets<'_, '_> {
+    pub fn of(&self, ship: Entity) -> Option<Vec2> {
+        match self.behaviour.get() {
+            Behaviour::Seek | Behaviour::Arrive | Behaviour::Flee => Some(self.mouse_pos.0),
+            Behaviour::Pursue | Behaviour::Evade => {
+                self.pursue_target.single().ok().map(|position| position.0)
+            }
+            Behaviour::Queue => self
+                .queue_progress
+                .get(ship)
+                .ok()
+                .map(|progress| progress.waypoint()),
+            Behaviour::NeuroEvolve | Behaviour::Brain => {
+                self.brain_targets.get(ship).ok().map(|target| target.0)
+            }
+            _ => None,
+        }
+    }
+}
 
+/// Angle around the wander circle, so each ship wanders on its own
 #[derive(Component)]
-struct WanderRadius;
+pub struct WanderTheta(pub f32);
 
 #[derive(Component)]
-struct PursueTarget;
+pub struct Ship;
 
+/// Moving target for pursue and evade
 #[derive(Component)]
-struct PursueOffset;
+pub struct PursueTarget;
 
 #[derive(Component)]
 pub struct WrapEdges;
 
-fn setup(
-    mut commands: Commands,
-    debug: Res<Debug>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    if debug.0 {
-        let wander_radius = 50.;
-        let wander_circle = Circle::new(wander_radius);
-        let yellowish: Color = YELLOW_GREEN.into();
-
-        commands.spawn((
-            Mesh2d(meshes.add(wander_circle)),
-            MeshMaterial2d(materials.add(ColorMaterial::from(yellowish))),
-            Transform::from_xyz(0., 0., 0.),
-            WanderRadius,
-            RigidBody::Kinematic,
-        ));
+// before steering, so the behaviours see every wrapping ship inside the bounds
+fn wrap_edges_system(mut query: Query<&mut Position, With<WrapEdges>>, bounds: Res<WorldBounds>) {
+    let half_max_width = bounds.half_size.x;
+    let half_max_height = bounds.half_size.y;
+
+    // bevy screen centre is 0,0
+    for mut position in &mut query {
+        if position.x > half_max_width {
+            position.x = -(half_max_width);
+        } else if position.x < (-half_max_width) {
+            position.x = half_max_width;
+        }
 
-        let target_radius = 5.;
-        let circle = Circle::new(target_radius);
-        let yellow: Color = YELLOW.into();
-        commands.spawn((
-            Mesh2d(meshes.add(circle)),
-            MeshMaterial2d(materials.add(ColorMaterial::from(yellow))),
-            Transform::from_xyz(0., 0., 0.),
-            WanderTarget,
-            RigidBody::Kinematic,
-        ));
+        if position.y > half_max_height {
+            position.y = -(half_max_height);
+        } else if position.y < (-half_max_height) {
+            position.y = half_max_height;
+        }
     }
 }
 
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Percent(40.0),
+            ..default()
+        },
+        DebugText,
+    ));
+}
+
+// vehicles turn themselves
+#[allow(clippy::type_complexity)]
 fn rotate_system(
-    mut query: Query<(&LinearVelocity, &MaxAngularSpeed, &mut Rotation), With<Ship>>,
+    mut query: Query<
+        (&LinearVelocity, &MaxAngularSpeed, &mut Rotation),
+        (With<Ship>, Without<Vehicle>),
+    >,
     time: Res<Time>,
 ) {
     for (velocity, max_angular_speed, mut rotation) in &mut query {
         // rotate to face direction of travel
         *rotation = rotation.slerp(
-            Rotation::radians(heading(velocity.0)),
+            Rotation::radians(heading_to_rotation(heading(velocity.0))),
             max_angular_speed.0 * time.delta_secs(),
         );
     }
 }
 
-// https://natureofcode.com/autonomous-agents/#example-51-seeking-a-target
 fn seek_system(
-    mut query: Query<(&mut LinearVelocity, &MaxLinearSpeed, &Position), With<Ship>>,
+    mut query: Query<(SteeringQuery, VehicleQuery), With<Ship>>,
     mouse_pos: Res<MousePos>,
-    time: Res<Time>,
+    bounds: Res<WorldBounds>,
 ) {
-    for (mut velocity, max_linear_speed, position) in &mut query {
-        let steer = seek(&mouse_pos.0, &velocity.0, max_linear_speed.0, &position.0);
-        velocity.0 += steer * time.delta_secs();
-    }
-}
-
-fn seek(target: &Vec2, velocity: &Vec2, max_linear_speed: f32, position: &Vec2) -> Vec2 {
-    let mut to_cursor = target - position;
-    to_cursor = set_magnitude(to_cursor, max_linear_speed);
-
-    to_cursor - velocity
-}
-
-fn arrive_system(
-    mut query: Query<(&mut LinearVelocity, &MaxLinearSpeed, &Position), With<Ship>>,
-    target: Res<MousePos>,
-) {
-    for (mut velocity, max_linear_speed, position) in &mut query {
-        let mut desired = target.0 - position.0;
-        let d = desired.length();
+    let seek = Seek {
+        target: mouse_pos.0,
+    };
 
-        let arrival_radius = 60.;
-        if d < arrival_radius {
-            // val, original min, original max, new range min, new range max
-            let adjusted_magnitude = adjust_magnitude(d, 0., max_linear_speed.0, 0., 100.);
-            desired = set_magnitude(desired, adjusted_magnitude);
-        } else {
-            desired = set_magnitude(desired, max_linear_speed.0);
+    query.par_iter_mut().for_each(|(mut steering, vehicle)| {
+        if vehicle.is_due() {
+            steering.add("seek", seek.steer(&vehicle.snapshot(&bounds)));
         }
+    });
+}
 
-        let steer = desired - velocity.0;
-
-        velocity.0 += steer;
+fn wander(theta: &WanderTheta) -> Wander {
+    Wander {
+        distance: 100.,
+        radius: 50.,
+        theta: theta.0,
     }
 }
 
-#[allow(clippy::type_complexity)]
 fn wander_system(
-    query: Query<(&mut LinearVelocity, &MaxLinearSpeed, &Position), With<Ship>>,
-    time: Res<Time>,
+    mut query: Query<(SteeringQuery, VehicleQuery, &mut WanderTheta), With<Ship>>,
     mut rng: GlobalEntropy<WyRand>,
-    debug: Res<Debug>,
-    mut debug_query: Query<&mut Position, (With<WanderTarget>, Without<Ship>)>,
-    mut debug_radius: Query<
-        &mut Position,
-        (With<WanderRadius>, Without<WanderTarget>, Without<Ship>),
-    >,
-    mut wander_theta: ResMut<Theta>,
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    bounds: Res<WorldBounds>,
 ) {
-    let distance_ahead = 100.;
-    let wander_radius = 50.;
-    let displace = rng.random_range(-0.3..0.3);
-
-    for (mut velocity, max_linear_speed, position) in query {
-        let mut circle_pos = set_magnitude(velocity.0, distance_ahead);
-        circle_pos += position.0;
-
-        let heading = heading(velocity.0);
-        let theta = wander_theta.0 + heading;
-
-        // polar-cartesian conversion
-        let circle_offset = Vec2 {
-            x: wander_radius * cos(theta),
-            y: wander_radius * sin(theta),
-        };
-
-        let target = circle_pos + circle_offset;
-
-        // seek
-        let steer = seek(&target, &velocity, max_linear_speed.0, position);
-        velocity.0 += steer * time.delta_secs();
-
-        // if debug draw circles
-        if debug.0 {
-            for mut circle_position in debug_query.iter_mut() {
-                circle_position.0 = target;
-            }
-            for mut outer_circle in debug_radius.iter_mut() {
-                outer_circle.0 = circle_pos;
+    query
+        .par_iter_mut()
+        .for_each(|(mut steering, vehicle, wander_theta)| {
+            if vehicle.is_due() {
+                steering.add(
+                    "wander",
+                    wander(&wander_theta).steer(&vehicle.snapshot(&bounds)),
+                );
             }
+        });
+
+    // one at a time, so the random numbers go to the same ships every run
+    for (_, vehicle, mut wander_theta) in &mut query {
+        if overlay.shows(DebugCategory::Wander) {
+            let vehicle = vehicle.snapshot(&bounds);
+            let wander = wander(&wander_theta);
+            let centre = wander.centre(&vehicle);
+            gizmos.line_2d(vehicle.position, centre, YELLOW_GREEN);
+            gizmos.circle_2d(centre, wander.radius, YELLOW_GREEN);
+            gizmos.circle_2d(wander.target(&vehicle), 5., YELLOW);
         }
-        wander_theta.0 += displace;
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use crate::steering_plugin::seek;
-    use bevy::prelude::*;
-
-    #[test]
-    fn seek_should_return_vec2() {
-        let target = Vec2::new(10.0, -12.0);
-        let velocity = Vec2::new(180.0, -123.0);
-        let max_speed = 14.0;
-        let position = Vec2::new(14.0, 18.0);
-
-        assert_eq!(
-            seek(&target, &velocity, max_speed, &position),
-            Vec2::new(-181.8503, 109.12281)
-        );
-
-        let target = Vec2::new(-102.0, 130.0);
-
-        assert_eq!(
-            seek(&target, &velocity, max_speed, &position),
-            Vec2::new(-190.07162, 132.72432)
-        );
-
-        let target = Vec2::new(10.0, -12.0);
-        let max_speed = 2.0;
-
-        assert_eq!(
-            seek(&target, &velocity, max_speed, &position),
-            Vec2::new(-180.26433, 121.01755)
-        );
+        wander_theta.0 += rng.random_range(-0.3..0.3);
     }
 }
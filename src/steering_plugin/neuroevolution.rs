@@ -0,0 +1,508 @@
+// evolves brains which steer to a target using ray cast sensors, and runs
+// a saved brain interactively
+use avian2d::prelude::*;
+use bevy::app::AppExit;
+use bevy::color::palettes::css::{ORANGE, SLATE_GRAY};
+use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+use std::f32::consts::PI;
+use std::path::PathBuf;
+
+use crate::game_plugin::{ShipAssets, ship_bundle};
+use crate::input_plugin::MousePos;
+use crate::steering_plugin::brain::Brain;
+use crate::steering_plugin::evolution::{GenerationLimit, select};
+use crate::steering_plugin::forces::SteeringQuery;
+use crate::steering_plugin::lod::{LodState, is_due};
+use crate::steering_plugin::orca::Obstacle;
+use crate::steering_plugin::{Ship, WorldBounds};
+
+const POPULATION: usize = 30;
+// seconds of simulation per generation
+const GENERATION_LENGTH: f32 = 15.;
+const MUTATION_RATE: f32 = 0.05;
+
+// sensor directions relative to the direction of travel
+const RAY_ANGLES: [f32; 5] = [-PI / 3., -PI / 6., 0., PI / 6., PI / 3.];
+const RAY_LENGTH: f32 = 150.;
+/// rays, direction to target and velocity in, steering force out
+pub const BRAIN_LAYERS: [usize; 3] = [RAY_ANGLES.len() + 4, 8, 2];
+
+// centre, size
+const OBSTACLES: [(Vec2, Vec2); 3] = [
+    (Vec2::new(-150., 100.), Vec2::new(40., 200.)),
+    (Vec2::new(50., -100.), Vec2::new(40., 200.)),
+    (Vec2::new(250., 150.), Vec2::new(150., 40.)),
+];
+const START: Vec2 = Vec2::new(-350., 0.);
+
+/// Where the best brain is saved after each generation, and loaded from
+#[derive(Resource)]
+pub struct BrainFile(pub PathBuf);
+
+impl Default for BrainFile {
+    fn default() -> Self {
+        Self(PathBuf::from("best_brain.txt"))
+    }
+}
+
+#[derive(Component)]
+pub struct BrainController(pub Brain);
+
+#[derive(Component)]
+pub struct BrainTarget(pub Vec2);
+
+#[derive(Component, Default)]
+pub struct BrainFitness {
+    targets_reached: u32,
+    // distance to the current target when it was set
+    start_distance: f32,
+}
+
+#[derive(Component)]
+pub struct Crashed;
+
+#[derive(Component)]
+pub struct BrainObstacle;
+
+/// Ships spawned for training
+#[derive(Component)]
+pub struct BrainShip;
+
+#[derive(Component)]
+pub struct NeuroText;
+
+#[derive(Resource, Default)]
+pub struct NeuroEvolution {
+    pub generation: u32,
+    elapsed: f32,
+    pub best_fitness: f32,
+}
+
+/// Turns sensor readings into network inputs, all roughly -1..1.
+/// Directions are relative to `forward` so the brain works whichever way the
+/// ship is facing. `ray_hits` are distances, None if the ray didn't hit anything
+pub fn sense(
+    forward: Vec2,
+    velocity: Vec2,
+    max_linear_speed: f32,
+    to_target: Vec2,
+    ray_hits: &[Option<f32>],
+) -> Vec<f32> {
+    let right = -forward.perp();
+    let local = |v: Vec2| Vec2::new(v.dot(right), v.dot(forward));
+
+    let mut inputs: Vec<f32> = ray_hits
+        .iter()
+        // closer obstacles give a bigger signal
+        .map(|hit| hit.map_or(0., |distance| 1. - distance / RAY_LENGTH))
+        .collect();
+
+    let target = local(to_target.normalize_or_zero());
+    let speed = local(velocity / max_linear_speed.max(f32::EPSILON));
+    inputs.extend([target.x, target.y, speed.x, speed.y]);
+
+    inputs
+}
+
+/// Steering force in world space from the brain's output
+pub fn think(brain: &Brain, inputs: &[f32], forward: Vec2, max_linear_speed: f32) -> Vec2 {
+    let outputs = brain.predict(inputs);
+    let right = -forward.perp();
+
+    (right * outputs[0] + forward * outputs[1]) * max_linear_speed
+}
+
+fn forward(velocity: Vec2) -> Vec2 {
+    velocity.try_normalize().unwrap_or(Vec2::Y)
+}
+
+fn inside_obstacle(point: Vec2, margin: f32) -> bool {
+    OBSTACLES.iter().any(|(centre, size)| {
+        let half = size / 2. + margin;
+        (point - centre).abs().cmple(half).all()
+    })
+}
+
+fn random_target(bounds: &WorldBounds, rng: &mut impl Rng) -> Vec2 {
+    loop {
+        let target = Vec2::new(
+            rng.random_range(-bounds.half_size.x..bounds.half_size.x),
+            rng.random_range(-bounds.half_size.y..bounds.half_size.y),
+        );
+        if !inside_obstacle(target, 20.) {
+            return target;
+        }
+    }
+}
+
+fn spawn_obstacles(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    let grey: Color = SLATE_GRAY.into();
+    let material = materials.add(ColorMaterial::from(grey));
+
+    for (centre, size) in OBSTACLES {
+        commands.spawn((
+            Mesh2d(meshes.add(Rectangle::from_size(size))),
+            MeshMaterial2d(material.clone()),
+            Transform::from_xyz(centre.x, centre.y, -1.),
+            RigidBody::Static,
+            Collider::rectangle(size.x, size.y),
+            Obstacle::rectangle(centre, size),
+            BrainObstacle,
+        ));
+    }
+}
+
+fn spawn_population(
+    commands: &mut Commands,
+    ship_assets: &ShipAssets,
+    bounds: &WorldBounds,
+    brains: Vec<Brain>,
+    rng: &mut impl Rng,
+) {
+    for brain in brains {
+        let target = random_target(bounds, rng);
+        commands.spawn(ship_bundle(ship_assets, START)).insert((
+            BrainController(brain),
+            BrainTarget(target),
+            BrainFitness {
+                targets_reached: 0,
+                start_distance: START.distance(target),
+            },
+            BrainShip,
+        ));
+    }
+}
+
+fn spawn_text(commands: &mut Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        NeuroText,
+    ));
+}
+
+pub fn on_start_neuroevolution(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    ship_assets: Res<ShipAssets>,
+    bounds: Res<WorldBounds>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    spawn_obstacles(&mut commands, &mut meshes, &mut materials);
+
+    let brains = (0..POPULATION)
+        .map(|_| Brain::random(BRAIN_LAYERS.to_vec(), &mut *rng))
+        .collect();
+    spawn_population(&mut commands, &ship_assets, &bounds, brains, &mut *rng);
+    spawn_text(&mut commands);
+
+    commands.insert_resource(NeuroEvolution::default());
+}
+
+#[allow(clippy::type_complexity)]
+pub fn brain_steering_system(
+    mut query: Query<
+        (
+            SteeringQuery,
+            &LinearVelocity,
+            &MaxLinearSpeed,
+            &Position,
+            &BrainController,
+            &BrainTarget,
+            Option<&LodState>,
+        ),
+        Without<Crashed>,
+    >,
+    ships: Query<Entity, With<Ship>>,
+    spatial_query: SpatialQuery,
+) {
+    // only obstacles should show up on the sensors
+    let filter = SpatialQueryFilter::from_excluded_entities(ships);
+
+    for (mut steering, velocity, max_linear_speed, position, brain, target, lod) in &mut query {
+        if !is_due(lod) {
+            continue;
+        }
+        let forward = forward(velocity.0);
+
+        let ray_hits: Vec<Option<f32>> = RAY_ANGLES
+            .iter()
+            .map(|angle| {
+                let direction = Dir2::new(Rot2::radians(*angle) * forward).unwrap_or(Dir2::Y);
+                spatial_query
+                    .cast_ray(position.0, direction, RAY_LENGTH, true, &filter)
+                    .map(|hit| hit.distance)
+            })
+            .collect();
+
+        let inputs = sense(
+            forward,
+            velocity.0,
+            max_linear_speed.0,
+            target.0 - position.0,
+            &ray_hits,
+        );
+        steering.add(
+            "brain",
+            think(&brain.0, &inputs, forward, max_linear_speed.0),
+        );
+    }
+}
+
+pub fn training_target_system(
+    mut query: Query<(&Position, &mut BrainTarget, &mut BrainFitness), Without<Crashed>>,
+    bounds: Res<WorldBounds>,
+    mut rng: GlobalEntropy<WyRand>,
+    mut gizmos: Gizmos,
+) {
+    let reached_distance = 20.;
+
+    for (position, mut target, mut fitness) in &mut query {
+        if position.0.distance(target.0) < reached_distance {
+            target.0 = random_target(&bounds, &mut *rng);
+            fitness.targets_reached += 1;
+            fitness.start_distance = position.0.distance(target.0);
+        }
+
+        gizmos.circle_2d(target.0, 5., ORANGE);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn crash_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Position, &mut LinearVelocity), (With<BrainShip>, Without<Crashed>)>,
+    obstacles: Query<(), With<BrainObstacle>>,
+    spatial_query: SpatialQuery,
+) {
+    let ship = Collider::circle(9.);
+
+    for (entity, position, mut velocity) in &mut query {
+        let hit = spatial_query
+            .shape_intersections(&ship, position.0, 0., &SpatialQueryFilter::default())
+            .into_iter()
+            .any(|other| obstacles.contains(other));
+
+        if hit {
+            velocity.0 = Vec2::ZERO;
+            commands.entity(entity).insert(Crashed);
+        }
+    }
+}
+
+fn fitness(fitness: &BrainFitness, position: Vec2, target: Vec2, crashed: bool) -> f32 {
+    // part marks for getting closer to the current target
+    let progress = if fitness.start_distance > 0. {
+        (1. - position.distance(target) / fitness.start_distance).max(0.)
+    } else {
+        0.
+    };
+    let score = fitness.targets_reached as f32 + progress;
+
+    if crashed { score * 0.5 } else { score }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn neuro_generation_system(
+    mut commands: Commands,
+    ships: Query<(
+        Entity,
+        &BrainController,
+        &BrainFitness,
+        &Position,
+        &BrainTarget,
+        Has<Crashed>,
+    )>,
+    mut evolution: ResMut<NeuroEvolution>,
+    ship_assets: Res<ShipAssets>,
+    bounds: Res<WorldBounds>,
+    brain_file: Res<BrainFile>,
+    mut rng: GlobalEntropy<WyRand>,
+    time: Res<Time>,
+) {
+    evolution.elapsed += time.delta_secs();
+
+    let all_crashed = ships.iter().all(|(.., crashed)| crashed);
+    if evolution.elapsed < GENERATION_LENGTH && !all_crashed {
+        return;
+    }
+
+    let mut previous = vec![];
+    for (entity, brain, brain_fitness, position, target, crashed) in &ships {
+        previous.push((
+            brain.0.clone(),
+            fitness(brain_fitness, position.0, target.0, crashed),
+        ));
+        commands.entity(entity).despawn();
+    }
+
+    let Some((best_brain, best)) = previous
+        .iter()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .cloned()
+    else {
+        return;
+    };
+
+    info!(
+        "Generation {}: best fitness {:.2}",
+        evolution.generation, best
+    );
+    if let Err(error) = best_brain.save(&brain_file.0) {
+        warn!("Couldn't save brain to {}: {error}", brain_file.0.display());
+    }
+
+    let fitness: Vec<f32> = previous.iter().map(|(_, f)| *f).collect();
+    let brains = (0..POPULATION)
+        .map(|_| {
+            let a = &previous[select(&fitness, &mut *rng)].0;
+            let b = &previous[select(&fitness, &mut *rng)].0;
+            let mut child = a.crossover(b, &mut *rng);
+            child.mutate(MUTATION_RATE, &mut *rng);
+            child
+        })
+        .collect();
+    spawn_population(&mut commands, &ship_assets, &bounds, brains, &mut *rng);
+
+    evolution.generation += 1;
+    evolution.elapsed = 0.;
+    evolution.best_fitness = best;
+}
+
+pub fn neuro_text_system(
+    mut text: Single<&mut Text, With<NeuroText>>,
+    evolution: Res<NeuroEvolution>,
+) {
+    text.0 = format!(
+        "Generation: {}\nBest fitness: {:.2}",
+        evolution.generation, evolution.best_fitness
+    );
+}
+
+pub fn neuro_generation_limit_system(
+    evolution: Res<NeuroEvolution>,
+    limit: Res<GenerationLimit>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if evolution.generation >= limit.0 {
+        exit.write(AppExit::Success);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn clean_up_neuroevolution(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<BrainShip>, With<BrainObstacle>, With<NeuroText>)>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+
+    commands.remove_resource::<NeuroEvolution>();
+}
+
+/// Brain loaded from `BrainFile` for the interactive mode
+#[derive(Resource)]
+pub struct LoadedBrain(Brain);
+
+pub fn on_start_brain(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    brain_file: Res<BrainFile>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    spawn_obstacles(&mut commands, &mut meshes, &mut materials);
+
+    let brain = match Brain::load(&brain_file.0) {
+        Ok(brain) if brain.layers == BRAIN_LAYERS => brain,
+        Ok(_) => {
+            warn!(
+                "{} was made for a different network, using a random brain",
+                brain_file.0.display()
+            );
+            Brain::random(BRAIN_LAYERS.to_vec(), &mut *rng)
+        }
+        Err(error) => {
+            warn!(
+                "Couldn't load {}: {error}, using a random brain",
+                brain_file.0.display()
+            );
+            Brain::random(BRAIN_LAYERS.to_vec(), &mut *rng)
+        }
+    };
+
+    commands.insert_resource(LoadedBrain(brain));
+}
+
+// every ship uses the loaded brain to steer to the mouse
+pub fn brain_target_system(
+    mut commands: Commands,
+    mut with_brain: Query<&mut BrainTarget, With<Ship>>,
+    without_brain: Query<Entity, (With<Ship>, Without<BrainController>)>,
+    brain: Res<LoadedBrain>,
+    mouse_pos: Res<MousePos>,
+) {
+    for mut target in &mut with_brain {
+        target.0 = mouse_pos.0;
+    }
+
+    for ship in &without_brain {
+        commands
+            .entity(ship)
+            .insert((BrainController(brain.0.clone()), BrainTarget(mouse_pos.0)));
+    }
+}
+
+pub fn clean_up_brain(
+    mut commands: Commands,
+    ships: Query<Entity, With<BrainController>>,
+    obstacles: Query<Entity, With<BrainObstacle>>,
+) {
+    for ship in &ships {
+        commands
+            .entity(ship)
+            .remove::<(BrainController, BrainTarget)>();
+    }
+    for obstacle in &obstacles {
+        commands.entity(obstacle).despawn();
+    }
+
+    commands.remove_resource::<LoadedBrain>();
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::neuroevolution::{BRAIN_LAYERS, sense};
+    use bevy::prelude::*;
+
+    #[test]
+    fn sense_should_be_relative_to_heading() {
+        let hits = [None, None, Some(75.), None, None];
+
+        // facing up, target to the right
+        let inputs = sense(Vec2::Y, Vec2::new(0., 50.), 100., Vec2::new(10., 0.), &hits);
+        assert_eq!(inputs.len(), BRAIN_LAYERS[0]);
+        assert_eq!(inputs, vec![0., 0., 0.5, 0., 0., 1., 0., 0., 0.5]);
+
+        // facing right, target to the right is straight ahead
+        let inputs = sense(Vec2::X, Vec2::new(50., 0.), 100., Vec2::new(10., 0.), &hits);
+        assert_eq!(&inputs[5..], &[0., 1., 0., 0.5]);
+    }
+}
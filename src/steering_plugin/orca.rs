@@ -0,0 +1,641 @@
+// Optimal reciprocal collision avoidance
+// based on RVO2 https://gamma.cs.unc.edu/RVO2/
+use avian2d::prelude::*;
+use bevy::color::palettes::css::SLATE_GRAY;
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::*;
+
+use crate::math::project_onto_segment;
+use crate::steering_plugin::behaviours::{Space, VehicleQuery};
+use crate::steering_plugin::debug::{DebugCategory, DebugOverlay, draw_perception};
+use crate::steering_plugin::forces::{LastSteering, Steering, limit_steering};
+use crate::steering_plugin::{Ship, WorldBounds};
+
+const EPSILON: f32 = 0.00001;
+
+/// Turns ORCA avoidance on or off for every ship with an `Avoidance` component
+#[derive(Resource)]
+pub struct LocalAvoidance(pub bool);
+
+/// Per-ship avoidance settings
+#[derive(Component)]
+pub struct Avoidance {
+    pub radius: f32,
+    // how far ahead (in seconds) to look for other agents
+    pub time_horizon: f32,
+    // how far ahead (in seconds) to look for obstacles
+    pub obstacle_time_horizon: f32,
+    pub neighbour_distance: f32,
+}
+
+impl Default for Avoidance {
+    fn default() -> Self {
+        Self {
+            radius: 10.,
+            time_horizon: 2.,
+            obstacle_time_horizon: 1.,
+            neighbour_distance: 150.,
+        }
+    }
+}
+
+/// Static polygon to avoid, vertices in world space
+#[derive(Component)]
+pub struct Obstacle(pub Vec<Vec2>);
+
+impl Obstacle {
+    /// Box centred on `centre`, vertices anticlockwise
+    pub fn rectangle(centre: Vec2, size: Vec2) -> Self {
+        let half_size = size / 2.;
+
+        Self(vec![
+            centre + Vec2::new(-half_size.x, -half_size.y),
+            centre + Vec2::new(half_size.x, -half_size.y),
+            centre + Vec2::new(half_size.x, half_size.y),
+            centre + Vec2::new(-half_size.x, half_size.y),
+        ])
+    }
+}
+
+#[derive(Component)]
+pub struct AvoidanceObstacle;
+
+/// Half-plane of permitted velocities, everything to the left of `direction`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Line {
+    pub point: Vec2,
+    pub direction: Vec2,
+}
+
+/// Snapshot of an agent used when building constraints
+#[derive(Debug, Clone, Copy)]
+pub struct Agent {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub radius: f32,
+    /// Which way round to measure to other agents
+    pub space: Space,
+}
+
+/// Constraint on this agent's velocity from another moving agent.
+/// Each agent takes half the responsibility for avoiding the collision.
+pub fn agent_line(agent: &Agent, other: &Agent, time_horizon: f32, time_step: f32) -> Line {
+    let relative_position = agent.space.delta(agent.position, other.position);
+    let relative_velocity = agent.velocity - other.velocity;
+    let combined_radius = agent.radius + other.radius;
+
+    let (direction, u) = velocity_obstacle(
+        relative_position,
+        relative_velocity,
+        combined_radius,
+        time_horizon,
+        time_step,
+    );
+
+    Line {
+        point: agent.velocity + 0.5 * u,
+        direction,
+    }
+}
+
+/// Constraint on this agent's velocity from a fixed point, eg the closest
+/// part of an obstacle. Static points don't move out of the way, so the agent
+/// takes full responsibility.
+pub fn point_line(agent: &Agent, point: Vec2, time_horizon: f32, time_step: f32) -> Line {
+    let (direction, u) = velocity_obstacle(
+        point - agent.position,
+        agent.velocity,
+        agent.radius,
+        time_horizon,
+        time_step,
+    );
+
+    Line {
+        point: agent.velocity + u,
+        direction,
+    }
+}
+
+// returns line direction and smallest change in velocity needed to leave the
+// truncated velocity obstacle
+fn velocity_obstacle(
+    relative_position: Vec2,
+    relative_velocity: Vec2,
+    combined_radius: f32,
+    time_horizon: f32,
+    time_step: f32,
+) -> (Vec2, Vec2) {
+    let distance_sq = relative_position.length_squared();
+    let combined_radius_sq = combined_radius * combined_radius;
+
+    if distance_sq > combined_radius_sq {
+        // no collision yet
+        let inv_time_horizon = 1. / time_horizon;
+
+        // vector from cutoff centre to relative velocity
+        let w = relative_velocity - inv_time_horizon * relative_position;
+        let w_length_sq = w.length_squared();
+        let dot = w.dot(relative_position);
+
+        if dot < 0. && dot * dot > combined_radius_sq * w_length_sq {
+            // project on cut-off circle
+            let w_length = w_length_sq.sqrt();
+            let unit_w = w / w_length;
+
+            (
+                Vec2::new(unit_w.y, -unit_w.x),
+                (combined_radius * inv_time_horizon - w_length) * unit_w,
+            )
+        } else {
+            // project on legs
+            let leg = (distance_sq - combined_radius_sq).sqrt();
+
+            let direction = if relative_position.perp_dot(w) > 0. {
+                // left leg
+                Vec2::new(
+                    relative_position.x * leg - relative_position.y * combined_radius,
+                    relative_position.x * combined_radius + relative_position.y * leg,
+                ) / distance_sq
+            } else {
+                // right leg
+                -Vec2::new(
+                    relative_position.x * leg + relative_position.y * combined_radius,
+                    -relative_position.x * combined_radius + relative_position.y * leg,
+                ) / distance_sq
+            };
+
+            let dot = relative_velocity.dot(direction);
+            (direction, dot * direction - relative_velocity)
+        }
+    } else {
+        // already colliding, project on cut-off circle of this time step
+        let inv_time_step = 1. / time_step;
+
+        let w = relative_velocity - inv_time_step * relative_position;
+        let w_length = w.length();
+        let unit_w = w.normalize_or_zero();
+
+        (
+            Vec2::new(unit_w.y, -unit_w.x),
+            (combined_radius * inv_time_step - w_length) * unit_w,
+        )
+    }
+}
+
+/// Finds the velocity closest to `preferred` which satisfies all the constraints.
+/// The first `obstacle_lines` lines are treated as hard constraints if the
+/// problem is infeasible.
+pub fn solve(lines: &[Line], obstacle_lines: usize, max_speed: f32, preferred: Vec2) -> Vec2 {
+    let mut result = Vec2::ZERO;
+    let line_fail = linear_program_2(lines, max_speed, preferred, false, &mut result);
+
+    if line_fail < lines.len() {
+        linear_program_3(lines, obstacle_lines, line_fail, max_speed, &mut result);
+    }
+
+    result
+}
+
+// optimise along a single line, constrained by all lines before it
+fn linear_program_1(
+    lines: &[Line],
+    line_no: usize,
+    radius: f32,
+    opt_velocity: Vec2,
+    direction_opt: bool,
+    result: &mut Vec2,
+) -> bool {
+    let line = lines[line_no];
+    let dot = line.point.dot(line.direction);
+    let discriminant = dot * dot + radius * radius - line.point.length_squared();
+
+    if discriminant < 0. {
+        // max speed circle fully invalidates this line
+        return false;
+    }
+
+    let sqrt_discriminant = discriminant.sqrt();
+    let mut t_left = -dot - sqrt_discriminant;
+    let mut t_right = -dot + sqrt_discriminant;
+
+    for other in &lines[..line_no] {
+        let denominator = line.direction.perp_dot(other.direction);
+        let numerator = other.direction.perp_dot(line.point - other.point);
+
+        if denominator.abs() <= EPSILON {
+            // lines are (almost) parallel
+            if numerator < 0. {
+                return false;
+            }
+            continue;
+        }
+
+        let t = numerator / denominator;
+        if denominator >= 0. {
+            t_right = t_right.min(t);
+        } else {
+            t_left = t_left.max(t);
+        }
+
+        if t_left > t_right {
+            return false;
+        }
+    }
+
+    if direction_opt {
+        if opt_velocity.dot(line.direction) > 0. {
+            *result = line.point + t_right * line.direction;
+        } else {
+            *result = line.point + t_left * line.direction;
+        }
+    } else {
+        let t = line.direction.dot(opt_velocity - line.point);
+        *result = line.point + t.clamp(t_left, t_right) * line.direction;
+    }
+
+    true
+}
+
+// returns the index of the first line which couldn't be satisfied, or
+// lines.len() if all were
+fn linear_program_2(
+    lines: &[Line],
+    radius: f32,
+    opt_velocity: Vec2,
+    direction_opt: bool,
+    result: &mut Vec2,
+) -> usize {
+    if direction_opt {
+        // opt_velocity is a unit vector here
+        *result = opt_velocity * radius;
+    } else if opt_velocity.length_squared() > radius * radius {
+        *result = opt_velocity.normalize() * radius;
+    } else {
+        *result = opt_velocity;
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.direction.perp_dot(line.point - *result) > 0. {
+            // result doesn't satisfy this constraint
+            let previous = *result;
+            if !linear_program_1(lines, i, radius, opt_velocity, direction_opt, result) {
+                *result = previous;
+                return i;
+            }
+        }
+    }
+
+    lines.len()
+}
+
+// infeasible, so minimise the maximum penetration into the agent constraints
+fn linear_program_3(
+    lines: &[Line],
+    obstacle_lines: usize,
+    begin_line: usize,
+    radius: f32,
+    result: &mut Vec2,
+) {
+    let mut distance = 0.;
+
+    for i in begin_line..lines.len() {
+        if lines[i].direction.perp_dot(lines[i].point - *result) <= distance {
+            continue;
+        }
+
+        let mut projected: Vec<Line> = lines[..obstacle_lines].to_vec();
+
+        for j in obstacle_lines..i {
+            let determinant = lines[i].direction.perp_dot(lines[j].direction);
+
+            let point = if determinant.abs() <= EPSILON {
+                if lines[i].direction.dot(lines[j].direction) > 0. {
+                    // same direction
+                    continue;
+                }
+                // opposite direction
+                0.5 * (lines[i].point + lines[j].point)
+            } else {
+                lines[i].point
+                    + (lines[j].direction.perp_dot(lines[i].point - lines[j].point) / determinant)
+                        * lines[i].direction
+            };
+
+            projected.push(Line {
+                point,
+                direction: (lines[j].direction - lines[i].direction).normalize_or_zero(),
+            });
+        }
+
+        let previous = *result;
+        let direction = Vec2::new(-lines[i].direction.y, lines[i].direction.x);
+        if linear_program_2(&projected, radius, direction, true, result) < projected.len() {
+            // should only happen because of floating point error
+            *result = previous;
+        }
+
+        distance = lines[i].direction.perp_dot(lines[i].point - *result);
+    }
+}
+
+// other agents within the avoidance's neighbour distance of `agent`
+fn neighbours<'a>(
+    agents: &'a [(Entity, Agent)],
+    entity: Entity,
+    agent: &Agent,
+    avoidance: &Avoidance,
+) -> impl Iterator<Item = &'a Agent> {
+    let distance = avoidance.neighbour_distance;
+    let position = agent.position;
+    let space = agent.space;
+
+    agents
+        .iter()
+        .filter(move |(other_entity, other)| {
+            *other_entity != entity && space.distance(position, other.position) <= distance
+        })
+        .map(|(_, other)| other)
+}
+
+/// Steering which reaches `avoiding` from `velocity` in one `time_step`, as far
+/// as `max_force` allows
+fn avoidance_steering(
+    avoiding: Vec2,
+    velocity: Vec2,
+    time_step: f32,
+    mass: f32,
+    max_force: f32,
+) -> Vec2 {
+    limit_steering((avoiding - velocity) / time_step, mass, max_force)
+}
+
+// runs after the behaviours, treating the velocity their steering would give as the
+// preferred velocity, then changes the steering to reach the avoiding velocity instead
+#[allow(clippy::type_complexity)]
+pub fn orca_system(
+    mut query: Query<
+        (
+            Entity,
+            VehicleQuery,
+            &mut Steering,
+            &mut LastSteering,
+            &Avoidance,
+            &ComputedMass,
+        ),
+        With<Ship>,
+    >,
+    obstacles: Query<&Obstacle>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+) {
+    let time_step = time.delta_secs();
+    if time_step <= 0. {
+        return;
+    }
+
+    let agents: Vec<(Entity, Agent)> = query
+        .iter()
+        .map(|(entity, vehicle, steering, _, avoidance, _)| {
+            let vehicle = vehicle.snapshot(&bounds);
+            (
+                entity,
+                Agent {
+                    position: vehicle.position,
+                    velocity: vehicle.velocity + steering.0 * time_step,
+                    radius: avoidance.radius,
+                    space: vehicle.space,
+                },
+            )
+        })
+        .collect();
+
+    let obstacles: Vec<&Obstacle> = obstacles.iter().collect();
+    let index: EntityHashMap<usize> = agents
+        .iter()
+        .enumerate()
+        .map(|(i, (entity, _))| (*entity, i))
+        .collect();
+
+    query.par_iter_mut().for_each(
+        |(entity, vehicle, mut steering, mut last, avoidance, mass)| {
+            let Some((_, agent)) = index.get(&entity).map(|i| &agents[*i]) else {
+                return;
+            };
+
+            let mut lines = vec![];
+
+            let obstacle_range =
+                avoidance.obstacle_time_horizon * vehicle.max_speed.0 + agent.radius;
+            for obstacle in &obstacles {
+                let vertices = &obstacle.0;
+                for i in 0..vertices.len() {
+                    let a = vertices[i];
+                    let b = vertices[(i + 1) % vertices.len()];
+                    let closest = project_onto_segment(agent.position, a, b);
+
+                    if closest.distance(agent.position) < obstacle_range {
+                        lines.push(point_line(
+                            agent,
+                            closest,
+                            avoidance.obstacle_time_horizon,
+                            time_step,
+                        ));
+                    }
+                }
+            }
+            let obstacle_lines = lines.len();
+
+            for other in neighbours(&agents, entity, agent, avoidance) {
+                lines.push(agent_line(agent, other, avoidance.time_horizon, time_step));
+            }
+
+            let avoiding = solve(&lines, obstacle_lines, vehicle.max_speed.0, agent.velocity);
+            // limiting has already run, so the avoiding steering is limited here
+            let avoiding_steering = avoidance_steering(
+                avoiding,
+                vehicle.velocity.0,
+                time_step,
+                mass.value(),
+                vehicle.max_force.0,
+            );
+            last.avoidance = avoiding_steering - steering.0;
+            steering.0 = avoiding_steering;
+        },
+    );
+
+    if overlay.shows(DebugCategory::Perception) {
+        for (entity, agent) in &agents {
+            let Ok((.., avoidance, _)) = query.get(*entity) else {
+                continue;
+            };
+            draw_perception(
+                &mut gizmos,
+                agent.position,
+                avoidance.neighbour_distance,
+                neighbours(&agents, *entity, agent, avoidance)
+                    .map(|other| agent.space.nearest(agent.position, other.position)),
+            );
+        }
+    }
+}
+
+// add some obstacles to avoid when avoidance is turned on
+pub fn sync_obstacles_system(
+    mut commands: Commands,
+    avoidance: Res<LocalAvoidance>,
+    existing: Query<Entity, With<AvoidanceObstacle>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !avoidance.0 {
+        for entity in &existing {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    if !existing.is_empty() {
+        return;
+    }
+
+    let grey: Color = SLATE_GRAY.into();
+    let half_size = Vec2::new(40., 60.);
+
+    for centre in [Vec2::new(-150., 50.), Vec2::new(150., -50.)] {
+        commands.spawn((
+            Mesh2d(meshes.add(Rectangle::from_size(half_size * 2.))),
+            MeshMaterial2d(materials.add(ColorMaterial::from(grey))),
+            Transform::from_xyz(centre.x, centre.y, -1.),
+            Obstacle::rectangle(centre, half_size * 2.),
+            AvoidanceObstacle,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::project_onto_segment;
+    use crate::steering_plugin::behaviours::Space;
+    use crate::steering_plugin::orca::{
+        Agent, EPSILON, agent_line, avoidance_steering, point_line, solve,
+    };
+    use bevy::prelude::*;
+
+    #[test]
+    fn solve_should_keep_preferred_velocity_without_constraints() {
+        let preferred = Vec2::new(30., 40.);
+        assert_eq!(solve(&[], 0, 100., preferred), preferred);
+
+        // clamped to max speed
+        assert!(
+            solve(&[], 0, 10., preferred).abs_diff_eq(Vec2::new(6., 8.), EPSILON),
+            "should be clamped to max speed"
+        );
+    }
+
+    #[test]
+    fn avoidance_steering_should_respect_max_steering_force() {
+        // a full reversal in one tick, far more than the force allows
+        let steering = avoidance_steering(
+            Vec2::new(-100., 0.),
+            Vec2::new(100., 0.),
+            1. / 64.,
+            2.,
+            400.,
+        );
+        assert!(steering.abs_diff_eq(Vec2::new(-200., 0.), EPSILON));
+
+        // small changes are made in full
+        let steering =
+            avoidance_steering(Vec2::new(101., 0.), Vec2::new(100., 0.), 1. / 64., 1., 400.);
+        assert!(steering.abs_diff_eq(Vec2::new(64., 0.), EPSILON));
+    }
+
+    #[test]
+    fn agents_on_collision_course_should_sidestep() {
+        let agent = Agent {
+            position: Vec2::new(-50., 0.),
+            velocity: Vec2::new(50., 0.),
+            radius: 10.,
+            space: Space::Plane,
+        };
+        let other = Agent {
+            position: Vec2::new(50., 0.),
+            velocity: Vec2::new(-50., 0.),
+            radius: 10.,
+            space: Space::Plane,
+        };
+
+        let line = agent_line(&agent, &other, 2., 1. / 60.);
+        let new_velocity = solve(&[line], 0, 50., agent.velocity);
+
+        // slows down or turns away, but never speeds up past max
+        assert!(new_velocity.length() <= 50. + EPSILON);
+        assert_ne!(new_velocity, agent.velocity);
+        // new velocity is on the permitted side of the line
+        assert!(line.direction.perp_dot(line.point - new_velocity) <= EPSILON);
+    }
+
+    #[test]
+    fn agents_across_a_wrapping_edge_should_avoid_each_other() {
+        let space = Space::Torus {
+            half_size: Vec2::new(400., 300.),
+        };
+        let agent = Agent {
+            position: Vec2::new(390., 0.),
+            velocity: Vec2::new(50., 0.),
+            radius: 10.,
+            space,
+        };
+        let other = Agent {
+            position: Vec2::new(-390., 0.),
+            velocity: Vec2::new(-50., 0.),
+            radius: 10.,
+            space,
+        };
+
+        // 20 apart through the edge, not 780 across the world
+        let line = agent_line(&agent, &other, 2., 1. / 60.);
+        assert_ne!(solve(&[line], 0, 50., agent.velocity), agent.velocity);
+    }
+
+    #[test]
+    fn distant_agents_should_not_change_velocity() {
+        let agent = Agent {
+            position: Vec2::new(0., 0.),
+            velocity: Vec2::new(20., 0.),
+            radius: 10.,
+            space: Space::Plane,
+        };
+        let other = Agent {
+            position: Vec2::new(0., 500.),
+            velocity: Vec2::new(20., 0.),
+            radius: 10.,
+            space: Space::Plane,
+        };
+
+        let line = agent_line(&agent, &other, 2., 1. / 60.);
+        assert_eq!(solve(&[line], 0, 50., agent.velocity), agent.velocity);
+    }
+
+    #[test]
+    fn should_not_move_into_obstacle() {
+        let agent = Agent {
+            position: Vec2::new(0., 0.),
+            velocity: Vec2::new(50., 0.),
+            radius: 10.,
+            space: Space::Plane,
+        };
+        let wall = project_onto_segment(agent.position, Vec2::new(30., -50.), Vec2::new(30., 50.));
+        assert_eq!(wall, Vec2::new(30., 0.));
+
+        let line = point_line(&agent, wall, 1., 1. / 60.);
+        let new_velocity = solve(&[line], 1, 50., agent.velocity);
+
+        // doesn't touch the wall within the time horizon
+        let closest = project_onto_segment(wall, agent.position, agent.position + new_velocity);
+        assert!(closest.distance(wall) >= agent.radius - 0.01);
+    }
+}
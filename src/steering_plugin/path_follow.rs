@@ -1,12 +1,10 @@
-use std::f32;
-
-use avian2d::prelude::*;
 use bevy::color::palettes::css::*;
-
 use bevy::prelude::*;
 
-use crate::steering_plugin::{Ship, seek};
-use crate::utils::set_magnitude;
+use crate::steering_plugin::behaviours::{FollowPath, Steer, VehicleQuery};
+use crate::steering_plugin::debug::{DebugCategory, DebugOverlay};
+use crate::steering_plugin::forces::SteeringQuery;
+use crate::steering_plugin::{Ship, WorldBounds};
 
 #[derive(Resource)]
 pub struct Path {
@@ -48,26 +46,12 @@ pub fn on_start_path(
     *path = new_path;
 }
 
-fn get_normal_point(pos: Vec2, a: Vec2, b: Vec2) -> Vec2 {
-    // vec pointing from a to pos
-    let vec_a = pos - a;
-
-    // vec pointing from a to b
-    let mut vec_b = b - a;
-
-    vec_b = vec_b.normalize();
-    let dot = vec_a.dot(vec_b);
-
-    vec_b *= dot;
-
-    a + vec_b
-}
-
 pub fn path_follow_system(
-    ship: Single<(&Position, &MaxLinearSpeed, &mut LinearVelocity), With<Ship>>,
+    mut ships: Query<(SteeringQuery, VehicleQuery), With<Ship>>,
     path: Res<Path>,
-    time: Res<Time>,
+    overlay: Res<DebugOverlay>,
     mut gizmos: Gizmos,
+    bounds: Res<WorldBounds>,
 ) {
     path.points.windows(2).for_each(|slice| {
         gizmos.line_2d(slice[0], slice[1], RED);
@@ -75,54 +59,29 @@ pub fn path_follow_system(
     // join end to start
     gizmos.line_2d(path.points[path.points.len() - 1], path.points[0], RED);
 
-    let (position, max_linear_speed, mut velocity) = ship.into_inner();
-
-    let distance_ahead = 15.;
-    let mut future = velocity.0;
-    future = set_magnitude(future, distance_ahead);
-    future += position.0;
-
-    let mut biggest_gap = f32::INFINITY;
-    let mut normal;
-    let mut target = Vec2::new(0., 0.);
-
-    for i in 0..path.points.len() {
-        // wrap around
-        let mut a = path.points[i];
-        let mut b = path.points[(i + 1) % path.points.len()];
-
-        let mut normal_point = get_normal_point(future, a, b);
-        let mut dir = b - a;
-
-        // if normal not in line segment, set to end point
-        if normal_point.x < a.x.min(b.x)
-            || normal_point.x > a.x.max(b.x)
-            || normal_point.y < a.y.min(b.y)
-            || normal_point.y > a.y.max(b.y)
-        {
-            normal_point = b;
+    let follow = FollowPath {
+        points: &path.points,
+        radius: path.radius,
+        look_ahead: 15.,
+    };
 
-            // get next line segment
-            a = path.points[(i + 1) % path.points.len()];
-            b = path.points[(i + 2) % path.points.len()];
-            dir = b - a;
+    ships.par_iter_mut().for_each(|(mut steering, vehicle)| {
+        if vehicle.is_due() {
+            steering.add("path follow", follow.steer(&vehicle.snapshot(&bounds)));
         }
+    });
 
-        let distance = future.distance(normal_point);
-        if distance < biggest_gap {
-            biggest_gap = distance;
-            normal = normal_point;
-
-            let adjusted_dir = set_magnitude(dir, distance_ahead);
-            target = normal + adjusted_dir;
-        }
+    if !overlay.shows(DebugCategory::Path) {
+        return;
     }
-
-    if biggest_gap > path.radius {
-        gizmos.circle_2d(target, 15., TEAL);
-
-        let steer = seek(&target, &velocity, max_linear_speed.0, position);
-        velocity.0 += steer * time.delta_secs();
+    for (_, vehicle) in &ships {
+        let vehicle = vehicle.snapshot(&bounds);
+        if let Some(projection) = follow.project(&vehicle) {
+            // where the ship will be, and the closest point to it on the path
+            gizmos.line_2d(vehicle.position, projection.future, TEAL);
+            gizmos.line_2d(projection.future, projection.normal, TEAL);
+            gizmos.circle_2d(projection.target, 15., TEAL);
+        }
     }
 }
 
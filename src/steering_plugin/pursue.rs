@@ -1,12 +1,14 @@
 use avian2d::prelude::*;
 use bevy::color::palettes::css::{YELLOW, YELLOW_GREEN};
-use bevy::prelude::ops::{atan2, cos, sin};
 use bevy::prelude::*;
 use bevy_rand::prelude::*;
 use rand::Rng;
 
-use crate::steering_plugin::{PursueOffset, PursueTarget, Ship, WrapEdges, seek};
-use crate::utils::set_magnitude;
+use crate::game_plugin::SHIP_MAX_SPEED;
+use crate::steering_plugin::behaviours::{Pursue, Steer, VehicleQuery};
+use crate::steering_plugin::debug::{DebugCategory, DebugOverlay};
+use crate::steering_plugin::forces::SteeringQuery;
+use crate::steering_plugin::{PursueTarget, Ship, WorldBounds, WrapEdges};
 
 // call on ship/target collision
 #[allow(clippy::complexity)]
@@ -18,7 +20,7 @@ pub fn reset_pursue_target(
     let (mut position, mut velocity) = target_query.into_inner();
 
     for CollisionStarted(_first, _second) in collision_event_reader.read() {
-        println!("Caught!");
+        debug!("Caught!");
 
         let random_x = rng.random_range(-20.0..20.);
         let random_y = rng.random_range(-20.0..20.);
@@ -34,53 +36,48 @@ pub fn reset_pursue_target(
 pub fn clean_up_pursue(
     mut commands: Commands,
     target_query: Single<Entity, With<PursueTarget>>,
-    offset_query: Single<Entity, With<PursueOffset>>,
-    ship: Single<&mut MaxLinearSpeed, With<Ship>>,
+    ships: Query<&mut MaxLinearSpeed, With<Ship>>,
 ) {
     let target = target_query.into_inner();
     commands.entity(target).despawn();
-    let offset = offset_query.into_inner();
-    commands.entity(offset).despawn();
 
     // reset max speed
-    let mut ship = ship.into_inner();
-    ship.0 = 200.;
+    for mut max_speed in ships {
+        max_speed.0 = SHIP_MAX_SPEED;
+    }
 }
 
 #[allow(clippy::type_complexity)]
 pub fn pursue_system(
-    ship_query: Single<
-        (&mut LinearVelocity, &MaxLinearSpeed, &Position),
-        (With<Ship>, Without<PursueTarget>),
-    >,
+    mut ship_query: Query<(SteeringQuery, VehicleQuery), (With<Ship>, Without<PursueTarget>)>,
     target_query: Single<(&Position, &LinearVelocity), (With<PursueTarget>, Without<Ship>)>,
-    offset_query: Single<&mut Position, (With<PursueOffset>, Without<Ship>, Without<PursueTarget>)>,
-    time: Res<Time>,
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    bounds: Res<WorldBounds>,
 ) {
-    let (mut velocity, max_speed, position) = ship_query.into_inner();
     let (target_pos, target_velocity) = target_query.into_inner();
-
-    // adjust this based on speed of target
-    let distance_ahead = 25.;
-
-    // target = circle
-    // offset = place ship needs to aim for
-    // heading() ends up 90deg off for target
-    let target_heading = atan2(target_velocity.0.y, target_velocity.0.x);
-    let mut offset_pos = set_magnitude(target_velocity.0, distance_ahead);
-    offset_pos += target_pos.0;
-
-    let mut target_offset = Vec2 {
-        x: distance_ahead * cos(target_heading),
-        y: distance_ahead * sin(target_heading),
+    let pursue = Pursue {
+        target_position: target_pos.0,
+        target_velocity: target_velocity.0,
     };
-    target_offset += offset_pos;
-    let mut offset = offset_query.into_inner();
-    offset.0 = target_offset;
 
-    let to_target = target_offset - position.0;
-    let steer = seek(&to_target, &velocity, max_speed.0, position);
-    velocity.0 += steer * time.delta_secs();
+    ship_query
+        .par_iter_mut()
+        .for_each(|(mut steering, vehicle)| {
+            if vehicle.is_due() {
+                steering.add("pursue", pursue.steer(&vehicle.snapshot(&bounds)));
+            }
+        });
+
+    if overlay.shows(DebugCategory::Prediction) {
+        for (_, vehicle) in &ship_query {
+            let vehicle = vehicle.snapshot(&bounds);
+            let prediction = pursue.prediction(&vehicle);
+            // the line may run off the edge, towards where the target reappears
+            gizmos.line_2d(target_pos.0, prediction, YELLOW);
+            gizmos.circle_2d(vehicle.space.wrap(prediction), 5., YELLOW);
+        }
+    }
 }
 
 pub fn on_start_pursue(
@@ -88,7 +85,7 @@ pub fn on_start_pursue(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut rng: GlobalEntropy<WyRand>,
-    ship: Single<&mut MaxLinearSpeed, With<Ship>>,
+    ships: Query<&mut MaxLinearSpeed, With<Ship>>,
 ) {
     // add target
     let target_radius = 15.;
@@ -97,10 +94,6 @@ pub fn on_start_pursue(
     let random_x = rng.random_range(-20.0..20.);
     let random_y = rng.random_range(-20.0..20.);
 
-    let offset_radius = 5.;
-    let offset_circle = Circle::new(offset_radius);
-    let offset_colour: Color = YELLOW.into();
-
     commands.spawn((
         Mesh2d(meshes.add(circle)),
         MeshMaterial2d(materials.add(ColorMaterial::from(yellow))),
@@ -114,14 +107,7 @@ pub fn on_start_pursue(
         Collider::circle(target_radius),
     ));
 
-    commands.spawn((
-        Mesh2d(meshes.add(offset_circle)),
-        MeshMaterial2d(materials.add(ColorMaterial::from(offset_colour))),
-        Transform::from_xyz(0., 0., 0.),
-        PursueOffset,
-        RigidBody::Kinematic,
-    ));
-
-    let mut ship = ship.into_inner();
-    ship.0 = 300.;
+    for mut max_speed in ships {
+        max_speed.0 = 300.;
+    }
 }
@@ -0,0 +1,240 @@
+use avian2d::prelude::*;
+use bevy::color::palettes::css::SLATE_GRAY;
+use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use rand::Rng;
+
+use crate::game_plugin::{ShipAssets, ship_bundle};
+use crate::math::set_magnitude;
+use crate::steering_plugin::behaviours::{Seek, Steer, VehicleQuery};
+use crate::steering_plugin::flock::{Neighbours, Separate};
+use crate::steering_plugin::forces::SteeringQuery;
+use crate::steering_plugin::orca::Obstacle;
+use crate::steering_plugin::{Ship, WorldBounds};
+
+// doorway in the middle, then out to the right
+const WAYPOINTS: [Vec2; 3] = [Vec2::new(-40., 0.), Vec2::new(40., 0.), Vec2::new(350., 0.)];
+
+/// Index into `WAYPOINTS` the ship is currently heading for
+#[derive(Component)]
+pub struct QueueProgress(usize);
+
+impl QueueProgress {
+    pub fn waypoint(&self) -> Vec2 {
+        WAYPOINTS[self.0]
+    }
+}
+
+/// Extra ships added for the queue scenario
+#[derive(Component)]
+pub struct QueueShip;
+
+#[derive(Component)]
+pub struct QueueWall;
+
+// https://gamedevelopment.tutsplus.com/understanding-steering-behaviors-queue--gamedev-14365t
+/// true if another agent is close to the point `look_ahead` in front of
+/// this one, or between the two
+pub fn should_brake(
+    position: Vec2,
+    velocity: Vec2,
+    neighbours: &Neighbours,
+    look_ahead: f32,
+    queue_radius: f32,
+) -> bool {
+    let ahead = position + set_magnitude(velocity, look_ahead);
+    let half_ahead = position + set_magnitude(velocity, look_ahead / 2.);
+
+    neighbours.positions().any(|other| {
+        other != position
+            && (ahead.distance(other) < queue_radius || half_ahead.distance(other) < queue_radius)
+    })
+}
+
+fn random_start(rng: &mut GlobalEntropy<WyRand>) -> Vec2 {
+    Vec2::new(
+        rng.random_range(-380.0..-150.),
+        rng.random_range(-250.0..250.),
+    )
+}
+
+pub fn on_start_queue(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut rng: GlobalEntropy<WyRand>,
+    ship_assets: Res<ShipAssets>,
+) {
+    // two walls leaving a narrow gap at 0,0
+    let gap = 30.;
+    let wall_width = 20.;
+    let wall_height = 300.;
+    let grey: Color = SLATE_GRAY.into();
+
+    for direction in [1., -1.] {
+        let centre = Vec2::new(0., direction * (gap + wall_height) / 2.);
+        let half_size = Vec2::new(wall_width, wall_height) / 2.;
+
+        commands.spawn((
+            Mesh2d(meshes.add(Rectangle::from_size(half_size * 2.))),
+            MeshMaterial2d(materials.add(ColorMaterial::from(grey))),
+            Transform::from_xyz(centre.x, centre.y, -1.),
+            RigidBody::Static,
+            Collider::rectangle(wall_width, wall_height),
+            Obstacle::rectangle(centre, half_size * 2.),
+            QueueWall,
+        ));
+    }
+
+    let queue_size = 20;
+    for _ in 0..queue_size {
+        let start = random_start(&mut rng);
+
+        commands
+            .spawn(ship_bundle(&ship_assets, start))
+            .insert((MaxLinearSpeed(150.0), QueueShip));
+    }
+}
+
+// includes ships added while queueing
+pub fn join_queue_system(
+    mut commands: Commands,
+    ships: Query<Entity, (With<Ship>, Without<QueueProgress>)>,
+) {
+    for ship in &ships {
+        commands.entity(ship).insert(QueueProgress(0));
+    }
+}
+
+pub fn queue_system(
+    mut query: Query<(SteeringQuery, VehicleQuery, &mut QueueProgress), With<Ship>>,
+    bounds: Res<WorldBounds>,
+) {
+    let look_ahead = 30.;
+    let queue_radius = 15.;
+    let desired_separation = 25.;
+    let brake_strength = 5.;
+    let waypoint_radius = 20.;
+
+    let neighbours: Neighbours = query
+        .iter()
+        .map(|(_, vehicle, _)| (vehicle.position.0, vehicle.velocity.0))
+        .collect();
+
+    let separate = Separate {
+        neighbours: &neighbours,
+        distance: desired_separation,
+    };
+
+    query
+        .par_iter_mut()
+        .for_each(|(mut steering, vehicle, mut progress)| {
+            let due = vehicle.is_due();
+            let vehicle = vehicle.snapshot(&bounds);
+
+            let target = WAYPOINTS[progress.0];
+            if vehicle.space.distance(vehicle.position, target) < waypoint_radius {
+                progress.0 += 1;
+                if progress.0 == WAYPOINTS.len() {
+                    // restarted by rejoin_queue_system
+                    return;
+                }
+            }
+            if !due {
+                return;
+            }
+
+            let separation = separate.steer(&vehicle);
+
+            if should_brake(
+                vehicle.position,
+                vehicle.velocity,
+                &neighbours,
+                look_ahead,
+                queue_radius,
+            ) {
+                // slow down and wait for the ship in front to move
+                steering.add("separate", separation);
+                steering.add("brake", -vehicle.velocity * brake_strength);
+            } else {
+                let seek = Seek {
+                    target: WAYPOINTS[progress.0],
+                };
+                steering.add("seek", seek.steer(&vehicle));
+                steering.add("separate", separation * 1.5);
+            }
+        });
+}
+
+// go round again. One at a time, so the random starts go to the same ships
+// every run
+pub fn rejoin_queue_system(
+    mut query: Query<(&mut Position, &mut LinearVelocity, &mut QueueProgress), With<Ship>>,
+    mut rng: GlobalEntropy<WyRand>,
+) {
+    for (mut position, mut velocity, mut progress) in &mut query {
+        if progress.0 == WAYPOINTS.len() {
+            progress.0 = 0;
+            position.0 = random_start(&mut rng);
+            velocity.0 = Vec2::ZERO;
+        }
+    }
+}
+
+pub fn clean_up_queue(
+    mut commands: Commands,
+    ships: Query<Entity, With<QueueShip>>,
+    walls: Query<Entity, With<QueueWall>>,
+    progress: Query<Entity, With<QueueProgress>>,
+) {
+    for entity in &progress {
+        commands.entity(entity).remove::<QueueProgress>();
+    }
+    for entity in ships.iter().chain(walls.iter()) {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::flock::Neighbours;
+    use crate::steering_plugin::queue::should_brake;
+    use bevy::prelude::*;
+
+    fn at(positions: &[Vec2]) -> Neighbours {
+        positions
+            .iter()
+            .map(|position| (*position, Vec2::ZERO))
+            .collect()
+    }
+
+    #[test]
+    fn should_brake_for_ship_directly_ahead() {
+        let position = Vec2::ZERO;
+        let velocity = Vec2::new(10., 0.);
+
+        assert!(should_brake(
+            position,
+            velocity,
+            &at(&[position, Vec2::new(28., 2.)]),
+            30.,
+            15.
+        ));
+        // behind
+        assert!(!should_brake(
+            position,
+            velocity,
+            &at(&[position, Vec2::new(-28., 0.)]),
+            30.,
+            15.
+        ));
+        // off to the side
+        assert!(!should_brake(
+            position,
+            velocity,
+            &at(&[position, Vec2::new(15., 40.)]),
+            30.,
+            15.
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+// every behaviour that can be switched to, so the menu, shortcuts and command
+// palette all come from the same list
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+use crate::steering_plugin::Behaviour;
+
+pub struct BehaviourEntry {
+    pub behaviour: Behaviour,
+    /// Shown on its button and searched by the command palette
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Behaviours in menu order
+#[derive(Resource)]
+pub struct BehaviourRegistry(pub Vec<BehaviourEntry>);
+
+impl BehaviourRegistry {
+    pub fn add(&mut self, behaviour: Behaviour, name: &'static str, description: &'static str) {
+        self.0.push(BehaviourEntry {
+            behaviour,
+            name,
+            description,
+        });
+    }
+
+    pub fn position(&self, behaviour: &Behaviour) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|entry| entry.behaviour == *behaviour)
+    }
+
+    /// Entries whose name contains the letters of `query` in order, ignoring
+    /// case. Names starting with the query come first
+    pub fn search(&self, query: &str) -> Vec<&BehaviourEntry> {
+        let query = query.to_lowercase();
+        let mut found: Vec<&BehaviourEntry> = self
+            .0
+            .iter()
+            .filter(|entry| {
+                let name = entry.name.to_lowercase();
+                let mut letters = name.chars();
+                query.chars().all(|c| letters.any(|n| n == c))
+            })
+            .collect();
+
+        // stable, so otherwise stays in menu order
+        found.sort_by_key(|entry| !entry.name.to_lowercase().starts_with(&query));
+        found
+    }
+}
+
+/// Name and description of a built in behaviour. Custom behaviours bring
+/// their own when they're registered
+fn describe(behaviour: &Behaviour) -> Option<(&'static str, &'static str)> {
+    // no wildcard, so a new behaviour can't be left out of the menu
+    let described = match behaviour {
+        Behaviour::Seek => ("Seek", "Head straight for the mouse"),
+        Behaviour::Arrive => ("Arrive", "Seek, slowing down near the mouse"),
+        Behaviour::Wander => ("Wander", "Drift around at random"),
+        Behaviour::Pursue => ("Pursue", "Chase where a moving target will be"),
+        Behaviour::Flee => ("Flee", "Run away from the mouse"),
+        Behaviour::Evade => ("Evade", "Run from where a chaser will be"),
+        Behaviour::PathFollow => ("Path", "Follow a looping path"),
+        Behaviour::Queue => ("Queue", "Queue through a gap in a wall"),
+        Behaviour::Ecosystem => ("Ecosystem", "Flocking prey hunted by predators"),
+        Behaviour::Evolve => ("Evolve", "Evolve ships to eat food and avoid poison"),
+        Behaviour::NeuroEvolve => ("Neuro", "Train neural network steering"),
+        Behaviour::Brain => ("Brain", "Steer with the best trained brain"),
+        Behaviour::Custom(_) => return None,
+    };
+
+    Some(described)
+}
+
+impl Default for BehaviourRegistry {
+    /// Every built in behaviour, in the order they're declared
+    fn default() -> Self {
+        let mut registry = Self(vec![]);
+        for behaviour in Behaviour::value_variants() {
+            if let Some((name, description)) = describe(behaviour) {
+                registry.add(behaviour.clone(), name, description);
+            }
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::Behaviour;
+    use crate::steering_plugin::registry::BehaviourRegistry;
+    use clap::ValueEnum;
+
+    #[test]
+    fn every_behaviour_should_be_registered() {
+        let registry = BehaviourRegistry::default();
+
+        for behaviour in Behaviour::value_variants() {
+            assert!(
+                registry.position(behaviour).is_some(),
+                "{behaviour:?} is missing"
+            );
+        }
+        assert_eq!(registry.0.len(), Behaviour::value_variants().len());
+    }
+
+    #[test]
+    fn search_should_match_letters_in_order_prefixes_first() {
+        let registry = BehaviourRegistry::default();
+        let names = |query| -> Vec<&str> {
+            registry
+                .search(query)
+                .iter()
+                .map(|entry| entry.name)
+                .collect()
+        };
+
+        assert_eq!(names("ev"), ["Evade", "Evolve"]);
+        assert_eq!(names("n"), ["Neuro", "Wander", "Brain"]);
+        assert_eq!(names("QUE"), ["Queue"]);
+        assert_eq!(names("xyz"), Vec::<&str>::new());
+        assert_eq!(names("").len(), 12);
+        assert_eq!(registry.position(&Behaviour::Seek), Some(0));
+    }
+}
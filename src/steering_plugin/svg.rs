@@ -0,0 +1,257 @@
+// the current scene as an SVG, for figures which don't need a screen
+use avian2d::prelude::*;
+use bevy::color::palettes::css::{BLUE, RED, SLATE_GRAY};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path as FilePath, PathBuf};
+
+use crate::game_plugin::SHIP_SHAPE;
+use crate::steering_plugin::orca::Obstacle;
+use crate::steering_plugin::path_follow::Path;
+use crate::steering_plugin::trails::Trail;
+use crate::steering_plugin::{Behaviour, Ship, SteeringTargets, WorldBounds};
+
+/// Where to save the scene when the app exits, used when running headless
+#[derive(Resource)]
+pub struct SvgExport(pub PathBuf);
+
+pub struct SvgShip {
+    pub position: Vec2,
+    /// radians, 0 facing up
+    pub rotation: f32,
+    pub colour: Srgba,
+}
+
+/// Everything drawn in the SVG, in world space
+#[derive(Default)]
+pub struct Scene {
+    pub half_size: Vec2,
+    pub ships: Vec<SvgShip>,
+    pub targets: Vec<Vec2>,
+    /// closed loop
+    pub path: Vec<Vec2>,
+    pub obstacles: Vec<Vec<Vec2>>,
+    pub trails: Vec<Vec<Vec2>>,
+}
+
+impl Scene {
+    // svg y goes down, with 0,0 in the top left
+    fn point(&self, world: Vec2) -> Vec2 {
+        Vec2::new(world.x + self.half_size.x, self.half_size.y - world.y)
+    }
+
+    fn points(&self, world: &[Vec2]) -> String {
+        let points: Vec<String> = world
+            .iter()
+            .map(|p| {
+                let p = self.point(*p);
+                format!("{:.1},{:.1}", p.x, p.y)
+            })
+            .collect();
+
+        points.join(" ")
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = self.half_size * 2.;
+        let mut svg = String::new();
+
+        // writing to a String can't fail
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            size.x, size.y, size.x, size.y
+        );
+        let _ = writeln!(
+            svg,
+            r##"<rect width="100%" height="100%" fill="#ffffff"/>"##
+        );
+
+        for obstacle in &self.obstacles {
+            let _ = writeln!(
+                svg,
+                r#"<polygon points="{}" fill="{}"/>"#,
+                self.points(obstacle),
+                SLATE_GRAY.to_hex()
+            );
+        }
+
+        if !self.path.is_empty() {
+            let _ = writeln!(
+                svg,
+                r#"<polygon points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+                self.points(&self.path),
+                RED.to_hex()
+            );
+        }
+
+        for trail in &self.trails {
+            // split where the ship wrapped round the edges
+            for part in trail.chunk_by(|a, b| a.distance(*b) < 50.) {
+                if part.len() < 2 {
+                    continue;
+                }
+                let _ = writeln!(
+                    svg,
+                    r##"<polyline points="{}" fill="none" stroke="#6699cc" stroke-opacity="0.6"/>"##,
+                    self.points(part)
+                );
+            }
+        }
+
+        for target in &self.targets {
+            let centre = self.point(*target);
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="6" fill="none" stroke="{}" stroke-width="2"/>"#,
+                centre.x,
+                centre.y,
+                RED.to_hex()
+            );
+        }
+
+        for ship in &self.ships {
+            let rotation = Rot2::radians(ship.rotation);
+            let vertices: Vec<Vec2> = SHIP_SHAPE
+                .iter()
+                .map(|vertex| ship.position + rotation * *vertex)
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"<polygon points="{}" fill="{}"/>"#,
+                self.points(&vertices),
+                ship.colour.to_hex()
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save(&self, path: &FilePath) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+
+    /// `save`, logging whether it worked, for the `S` key and `--svg`
+    pub fn export(&self, path: &FilePath) {
+        match self.save(path) {
+            Ok(()) => info!("Saved scene to {}", path.display()),
+            Err(e) => warn!("Couldn't save scene to {}: {e}", path.display()),
+        }
+    }
+}
+
+type SvgShipData = (
+    Entity,
+    &'static Position,
+    &'static Rotation,
+    Option<&'static MeshMaterial2d<ColorMaterial>>,
+    Option<&'static Trail>,
+);
+
+/// Everything needed to take a snapshot of the scene
+#[derive(SystemParam)]
+pub struct SceneQuery<'w, 's> {
+    ships: Query<'w, 's, SvgShipData, With<Ship>>,
+    obstacles: Query<'w, 's, &'static Obstacle>,
+    materials: Res<'w, Assets<ColorMaterial>>,
+    path: Res<'w, Path>,
+    bounds: Res<'w, WorldBounds>,
+    behaviour: Res<'w, State<Behaviour>>,
+    targets: SteeringTargets<'w, 's>,
+}
+
+impl SceneQuery<'_, '_> {
+    pub fn scene(&self) -> Scene {
+        let mut scene = Scene {
+            half_size: self.bounds.half_size,
+            obstacles: self.obstacles.iter().map(|o| o.0.clone()).collect(),
+            ..default()
+        };
+
+        if *self.behaviour.get() == Behaviour::PathFollow {
+            scene.path = self.path.points.clone();
+        }
+
+        for (entity, position, rotation, material, trail) in &self.ships {
+            let colour = material
+                .and_then(|material| self.materials.get(&material.0))
+                .map_or(BLUE, |material| material.color.to_srgba());
+
+            scene.ships.push(SvgShip {
+                position: position.0,
+                rotation: rotation.as_radians(),
+                colour,
+            });
+
+            if let Some(trail) = trail {
+                scene.trails.push(trail.0.iter().copied().collect());
+            }
+
+            // several ships often share a target
+            if let Some(target) = self.targets.of(entity)
+                && !scene.targets.iter().any(|t| t.distance(target) < 1.)
+            {
+                scene.targets.push(target);
+            }
+        }
+
+        scene
+    }
+}
+
+pub fn export_svg_on_exit_system(
+    mut exit: EventReader<AppExit>,
+    scene: SceneQuery,
+    export: Res<SvgExport>,
+) {
+    if exit.read().next().is_none() {
+        return;
+    }
+
+    scene.scene().export(&export.0);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::svg::{Scene, SvgShip};
+    use bevy::color::palettes::css::BLUE;
+    use bevy::prelude::*;
+
+    #[test]
+    fn svg_should_flip_y_and_include_everything() {
+        let scene = Scene {
+            half_size: Vec2::new(100., 50.),
+            ships: vec![SvgShip {
+                position: Vec2::ZERO,
+                rotation: 0.,
+                colour: BLUE,
+            }],
+            targets: vec![Vec2::new(10., 20.)],
+            path: vec![],
+            obstacles: vec![vec![Vec2::ZERO, Vec2::X, Vec2::Y]],
+            // wraps round the edge, so drawn as two lines
+            trails: vec![vec![
+                Vec2::new(-90., 0.),
+                Vec2::new(-95., 0.),
+                Vec2::new(95., 0.),
+                Vec2::new(90., 0.),
+            ]],
+        };
+
+        let svg = scene.to_svg();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"width="200" height="100""#));
+        assert!(svg.contains(r#"cx="110.0" cy="30.0""#));
+        // ship points up, so its nose is above the centre
+        assert!(svg.contains(r##"points="100.0,35.0 90.0,60.0 110.0,60.0" fill="#0000FF""##));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        // no path outside PathFollow
+        assert_eq!(svg.matches("stroke-width").count(), 1);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,258 @@
+// where ships have been - fading trails behind each ship, and a heatmap of
+// every cell visited since the behaviour last changed
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use image::{ImageResult, Rgb, RgbImage};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use crate::steering_plugin::{Ship, WorldBounds};
+
+#[derive(Resource)]
+pub struct ShowTrails(pub bool);
+
+#[derive(Resource)]
+pub struct ShowHeatmap(pub bool);
+
+/// Where to save the heatmap when the app exits, used when running headless
+#[derive(Resource)]
+pub struct HeatmapExport(pub PathBuf);
+
+const TRAIL_LENGTH: usize = 120;
+
+/// Recent positions, oldest first
+#[derive(Component, Default)]
+pub struct Trail(pub VecDeque<Vec2>);
+
+/// Number of times ships have been seen in each cell, row by row from the bottom left
+#[derive(Resource)]
+pub struct Heatmap {
+    pub cell_size: f32,
+    pub columns: usize,
+    pub rows: usize,
+    half_size: Vec2,
+    pub counts: Vec<u32>,
+}
+
+// pixels per cell when saved
+const IMAGE_SCALE: u32 = 10;
+
+impl Heatmap {
+    pub fn new(half_size: Vec2, cell_size: f32) -> Self {
+        let columns = (half_size.x * 2. / cell_size).ceil() as usize;
+        let rows = (half_size.y * 2. / cell_size).ceil() as usize;
+
+        Self {
+            cell_size,
+            columns,
+            rows,
+            half_size,
+            counts: vec![0; columns * rows],
+        }
+    }
+
+    /// Index into `counts`, or None outside the bounds
+    pub fn cell(&self, position: Vec2) -> Option<usize> {
+        let local = (position + self.half_size) / self.cell_size;
+        if local.x < 0. || local.y < 0. {
+            return None;
+        }
+
+        let (column, row) = (local.x as usize, local.y as usize);
+        (column < self.columns && row < self.rows).then_some(row * self.columns + column)
+    }
+
+    pub fn record(&mut self, position: Vec2) {
+        if let Some(cell) = self.cell(position) {
+            self.counts[cell] += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+    }
+
+    /// Centre of the cell in world space
+    pub fn centre(&self, cell: usize) -> Vec2 {
+        let column = (cell % self.columns) as f32;
+        let row = (cell / self.columns) as f32;
+
+        Vec2::new(column + 0.5, row + 0.5) * self.cell_size - self.half_size
+    }
+
+    /// For each cell, 0 for unvisited up to 1 for the busiest cell
+    pub fn heats(&self) -> Vec<f32> {
+        let max = self.counts.iter().max().copied().unwrap_or(0).max(1);
+
+        self.counts
+            .iter()
+            .map(|count| *count as f32 / max as f32)
+            .collect()
+    }
+
+    pub fn to_image(&self) -> RgbImage {
+        let mut image = RgbImage::new(
+            self.columns as u32 * IMAGE_SCALE,
+            self.rows as u32 * IMAGE_SCALE,
+        );
+        let heats = self.heats();
+
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let column = (x / IMAGE_SCALE) as usize;
+            // images start at the top
+            let row = self.rows - 1 - (y / IMAGE_SCALE) as usize;
+            let [r, g, b] = heat_colour(heats[row * self.columns + column]);
+
+            *pixel = Rgb([(r * 255.) as u8, (g * 255.) as u8, (b * 255.) as u8]);
+        }
+
+        image
+    }
+
+    pub fn save(&self, path: &Path) -> ImageResult<()> {
+        self.to_image().save(path)
+    }
+
+    /// `save`, logging whether it worked, for the `E` key and `--heatmap`
+    pub fn export(&self, path: &Path) {
+        match self.save(path) {
+            Ok(()) => info!("Saved heatmap to {}", path.display()),
+            Err(e) => warn!("Couldn't save heatmap to {}: {e}", path.display()),
+        }
+    }
+}
+
+impl FromWorld for Heatmap {
+    fn from_world(world: &mut World) -> Self {
+        let bounds = world.resource::<WorldBounds>();
+        Heatmap::new(bounds.half_size, 20.)
+    }
+}
+
+/// black -> red -> yellow -> white as `heat` goes from 0 to 1
+pub fn heat_colour(heat: f32) -> [f32; 3] {
+    let heat = heat.clamp(0., 1.) * 3.;
+    [
+        heat.min(1.),
+        (heat - 1.).clamp(0., 1.),
+        (heat - 2.).clamp(0., 1.),
+    ]
+}
+
+pub fn record_trails_system(
+    mut ships: Query<(&Position, &mut Trail), With<Ship>>,
+    show: Res<ShowTrails>,
+) {
+    if !show.0 {
+        return;
+    }
+
+    for (position, mut trail) in &mut ships {
+        if trail.0.len() == TRAIL_LENGTH {
+            trail.0.pop_front();
+        }
+        trail.0.push_back(position.0);
+    }
+}
+
+pub fn record_heatmap_system(ships: Query<&Position, With<Ship>>, mut heatmap: ResMut<Heatmap>) {
+    for position in &ships {
+        heatmap.record(position.0);
+    }
+}
+
+pub fn clear_heatmap_system(mut heatmap: ResMut<Heatmap>) {
+    heatmap.clear();
+}
+
+pub fn draw_trails_system(
+    mut ships: Query<&mut Trail, With<Ship>>,
+    show: Res<ShowTrails>,
+    mut gizmos: Gizmos,
+) {
+    // wrapping round the edges would draw a line right across the screen
+    let max_step = 50.;
+
+    for mut trail in &mut ships {
+        if !show.0 {
+            trail.0.clear();
+            continue;
+        }
+
+        let length = trail.0.len() as f32;
+        for (i, (a, b)) in trail.0.iter().zip(trail.0.iter().skip(1)).enumerate() {
+            if a.distance(*b) < max_step {
+                let alpha = i as f32 / length;
+                gizmos.line_2d(*a, *b, Color::srgba(0.6, 0.8, 1., alpha));
+            }
+        }
+    }
+}
+
+pub fn draw_heatmap_system(heatmap: Res<Heatmap>, show: Res<ShowHeatmap>, mut gizmos: Gizmos) {
+    if !show.0 {
+        return;
+    }
+
+    for (cell, heat) in heatmap.heats().into_iter().enumerate() {
+        if heat <= 0. {
+            continue;
+        }
+
+        let [r, g, b] = heat_colour(heat);
+        gizmos.rect_2d(
+            Isometry2d::from_translation(heatmap.centre(cell)),
+            Vec2::splat(heatmap.cell_size * 0.9),
+            Color::srgba(r, g, b, 0.5),
+        );
+    }
+}
+
+pub fn export_heatmap_on_exit_system(
+    mut exit: EventReader<AppExit>,
+    heatmap: Res<Heatmap>,
+    export: Res<HeatmapExport>,
+) {
+    if exit.read().next().is_none() {
+        return;
+    }
+
+    heatmap.export(&export.0);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::steering_plugin::trails::{Heatmap, heat_colour};
+    use bevy::prelude::*;
+
+    #[test]
+    fn heatmap_should_count_visits_per_cell() {
+        let mut heatmap = Heatmap::new(Vec2::new(100., 50.), 10.);
+        assert_eq!((heatmap.columns, heatmap.rows), (20, 10));
+
+        heatmap.record(Vec2::new(-95., -45.));
+        heatmap.record(Vec2::new(-91., -41.));
+        heatmap.record(Vec2::new(95., 45.));
+        // outside the bounds
+        heatmap.record(Vec2::new(500., 0.));
+
+        assert_eq!(heatmap.counts[0], 2);
+        assert_eq!(heatmap.counts[199], 1);
+        assert_eq!(heatmap.counts.iter().sum::<u32>(), 3);
+        assert_eq!(heatmap.centre(0), Vec2::new(-95., -45.));
+        assert_eq!(heatmap.heats()[199], 0.5);
+
+        let image = heatmap.to_image();
+        assert_eq!(image.dimensions(), (200, 100));
+        // bottom left is the hottest
+        assert_eq!(image.get_pixel(0, 99).0, [255, 255, 255]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0]);
+    }
+
+    #[test]
+    fn heat_colour_should_go_from_black_to_white() {
+        assert_eq!(heat_colour(0.), [0., 0., 0.]);
+        assert_eq!(heat_colour(0.5), [1., 0.5, 0.]);
+        assert_eq!(heat_colour(1.), [1., 1., 1.]);
+    }
+}
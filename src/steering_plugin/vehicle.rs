@@ -0,0 +1,157 @@
+// vehicles which can't strafe - steering becomes thrust along the ship's forward
+// axis plus a bounded turn, rather than an acceleration in any direction
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::math::{from_heading, rotation_to_heading};
+use crate::steering_plugin::Ship;
+use crate::steering_plugin::forces::Steering;
+
+/// Vehicle model for a ship. Ships without one can accelerate in any
+/// direction and just turn to face where they're going
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub enum Vehicle {
+    /// Turns like a bicycle - no tighter than `turning_radius`, and not at all
+    /// when stopped
+    Car { turning_radius: f32 },
+    /// Turns at up to `max_yaw_rate` radians per second at any speed
+    Boat { max_yaw_rate: f32 },
+}
+
+/// Vehicle model given to every ship, None for free movement
+#[derive(Resource)]
+pub struct VehicleModel(pub Option<Vehicle>);
+
+// fraction of the desired speed kept while facing the wrong way, so cars can
+// still drive round to face their target
+const CREEP: f32 = 0.25;
+
+/// New rotation and velocity of a vehicle after `dt` seconds of `steering`.
+/// `rotation` is in radians, 0 facing up like the ship mesh
+pub fn drive(
+    vehicle: Vehicle,
+    rotation: f32,
+    velocity: Vec2,
+    steering: Vec2,
+    dt: f32,
+) -> (f32, Vec2) {
+    let forward = from_heading(rotation_to_heading(rotation));
+    let speed = velocity.dot(forward).max(0.);
+
+    // steering is desired velocity - velocity
+    let desired = velocity + steering;
+    // 0 when there's nowhere to go
+    let error = forward.perp_dot(desired).atan2(forward.dot(desired));
+
+    let max_yaw_rate = match vehicle {
+        Vehicle::Car { turning_radius } => speed / turning_radius,
+        Vehicle::Boat { max_yaw_rate } => max_yaw_rate,
+    };
+    let yaw = (error / dt).clamp(-max_yaw_rate, max_yaw_rate) * dt;
+
+    // slow down for sharp turns, the thrust available is however hard the behaviours steer
+    let target_speed = desired.length() * error.cos().max(CREEP);
+    let thrust = steering.length() * dt;
+    let speed = speed + (target_speed - speed).clamp(-thrust, thrust);
+
+    let rotation = rotation + yaw;
+    (
+        rotation,
+        from_heading(rotation_to_heading(rotation)) * speed,
+    )
+}
+
+// runs before the steering is applied, replacing it with whatever gets the
+// vehicle to the velocity it can actually reach
+pub fn vehicle_system(
+    mut query: Query<(&Vehicle, &mut Steering, &LinearVelocity, &mut Rotation)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0. {
+        return;
+    }
+
+    for (vehicle, mut steering, velocity, mut rotation) in &mut query {
+        let (angle, new_velocity) =
+            drive(*vehicle, rotation.as_radians(), velocity.0, steering.0, dt);
+
+        *rotation = Rotation::radians(angle);
+        steering.0 = (new_velocity - velocity.0) / dt;
+    }
+}
+
+// also catches ships spawned since the model was last changed
+pub fn sync_vehicles_system(
+    mut commands: Commands,
+    ships: Query<(Entity, Option<&Vehicle>), With<Ship>>,
+    model: Res<VehicleModel>,
+) {
+    for (entity, vehicle) in &ships {
+        if vehicle == model.0.as_ref() {
+            continue;
+        }
+
+        match model.0 {
+            Some(wanted) => commands.entity(entity).insert(wanted),
+            None => commands.entity(entity).remove::<Vehicle>(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{from_heading, rotation_to_heading};
+    use crate::steering_plugin::vehicle::{Vehicle, drive};
+    use bevy::prelude::*;
+
+    #[test]
+    fn stopped_car_should_not_turn() {
+        let car = Vehicle::Car {
+            turning_radius: 40.,
+        };
+
+        // wants to go right, but isn't moving
+        let (rotation, velocity) = drive(car, 0., Vec2::ZERO, Vec2::new(100., 0.), 0.1);
+
+        assert_eq!(rotation, 0.);
+        // creeps forward to start turning
+        assert!(velocity.x.abs() < 0.001 && velocity.y > 0.);
+    }
+
+    #[test]
+    fn boat_should_turn_at_bounded_rate() {
+        let boat = Vehicle::Boat { max_yaw_rate: 1. };
+
+        let (rotation, _) = drive(boat, 0., Vec2::new(0., 50.), Vec2::new(100., -50.), 0.1);
+        // turning right is clockwise
+        assert!((rotation + 0.1).abs() < 0.001);
+
+        // small corrections happen straight away
+        let (rotation, _) = drive(boat, 0., Vec2::new(0., 50.), Vec2::new(0.1, 0.), 0.1);
+        assert!(rotation < 0. && rotation > -0.1);
+    }
+
+    #[test]
+    fn should_stay_still_without_steering() {
+        let boat = Vehicle::Boat { max_yaw_rate: 1. };
+
+        assert_eq!(
+            drive(boat, 0., Vec2::ZERO, Vec2::ZERO, 0.1),
+            (0., Vec2::ZERO)
+        );
+    }
+
+    #[test]
+    fn velocity_should_follow_heading() {
+        let car = Vehicle::Car {
+            turning_radius: 40.,
+        };
+
+        let (rotation, velocity) = drive(car, 0.3, Vec2::new(-10., 80.), Vec2::new(-50., 20.), 0.1);
+
+        let forward = from_heading(rotation_to_heading(rotation));
+        assert!(velocity.perp_dot(forward).abs() < 0.001);
+        assert!(velocity.dot(forward) > 0.);
+    }
+}
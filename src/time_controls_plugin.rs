@@ -0,0 +1,128 @@
+use bevy::app::FixedMain;
+use bevy::prelude::*;
+
+/// Pause, single step and slow motion. Steering and physics both run on fixed
+/// time, which follows `Time<Virtual>`, so they stay in step with each other
+pub struct TimeControlsPlugin;
+
+impl Plugin for TimeControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StepRequested(false))
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (time_controls_system, step_system, time_text_system).chain(),
+            );
+    }
+}
+
+pub const SPEEDS: [f32; 7] = [0.1, 0.25, 0.5, 1., 2., 5., 10.];
+
+/// Advance one fixed tick next frame, if paused
+#[derive(Resource)]
+pub struct StepRequested(pub bool);
+
+#[derive(Component)]
+struct TimeText;
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        TimeText,
+    ));
+}
+
+/// Next speed up or down from `current`, staying within SPEEDS
+pub fn change_speed(current: f32, faster: bool) -> f32 {
+    if faster {
+        SPEEDS
+            .into_iter()
+            .find(|speed| *speed > current)
+            .unwrap_or(SPEEDS[SPEEDS.len() - 1])
+    } else {
+        SPEEDS
+            .into_iter()
+            .rev()
+            .find(|speed| *speed < current)
+            .unwrap_or(SPEEDS[0])
+    }
+}
+
+fn time_controls_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut step: ResMut<StepRequested>,
+) {
+    if keyboard.just_pressed(KeyCode::Space) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::Period) {
+        // stepping only makes sense while paused
+        time.pause();
+        step.0 = true;
+    }
+
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        let speed = change_speed(time.relative_speed(), true);
+        time.set_relative_speed(speed);
+    }
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        let speed = change_speed(time.relative_speed(), false);
+        time.set_relative_speed(speed);
+    }
+}
+
+// does what the fixed main loop would for a single tick, as paused virtual
+// time never builds up enough for one
+fn step_system(world: &mut World) {
+    if !world.resource::<StepRequested>().0 {
+        return;
+    }
+    world.resource_mut::<StepRequested>().0 = false;
+
+    let timestep = world.resource::<Time<Fixed>>().timestep();
+    world.resource_mut::<Time<Fixed>>().advance_by(timestep);
+    *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+
+    world.run_schedule(FixedMain);
+
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
+
+fn time_text_system(mut text: Single<&mut Text, With<TimeText>>, time: Res<Time<Virtual>>) {
+    let speed = format!("{}x", time.relative_speed());
+
+    text.0 = if time.is_paused() {
+        format!("Paused ({speed})")
+    } else {
+        speed
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::time_controls_plugin::change_speed;
+
+    #[test]
+    fn change_speed_should_stay_in_range() {
+        assert_eq!(change_speed(1., true), 2.);
+        assert_eq!(change_speed(1., false), 0.5);
+        assert_eq!(change_speed(10., true), 10.);
+        assert_eq!(change_speed(0.1, false), 0.1);
+    }
+}
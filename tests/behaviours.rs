@@ -0,0 +1,347 @@
+// whole behaviours running in a headless app, with physics
+mod common;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use boids::game_plugin::SHIP_MAX_SPEED;
+use boids::math::project_onto_segment;
+use boids::steering_plugin::arrive::{ArriveSettings, Arrived, Settled};
+use boids::steering_plugin::behaviours::Deceleration;
+use boids::steering_plugin::forces::SteeringParts;
+use boids::steering_plugin::lod::{LodFocus, LodState, LodTier, SteeringLod};
+use boids::steering_plugin::orca::Obstacle;
+use boids::steering_plugin::path_follow::Path;
+use boids::steering_plugin::registry::BehaviourRegistry;
+use boids::steering_plugin::{Behaviour, PursueTarget, Ship};
+use clap::ValueEnum;
+use common::Harness;
+
+#[test]
+fn seek_should_converge_on_target() {
+    let target = Vec2::new(200., 0.);
+    let mut harness = Harness::new();
+    harness.mouse(target).behaviour(Behaviour::Seek);
+    let ship = harness.spawn_ship(Vec2::new(-200., -50.), Vec2::ZERO);
+
+    let mut closest = f32::INFINITY;
+    harness.run(4., |harness| {
+        closest = closest.min(harness.position(ship).distance(target));
+    });
+    assert!(closest < 10., "closest {closest}");
+
+    // overshoots, but each pass is shorter
+    harness.run(2., |harness| {
+        let distance = harness.position(ship).distance(target);
+        assert!(distance < 40., "{distance} from target");
+    });
+}
+
+#[test]
+fn seek_should_cross_wrapping_edge_to_nearby_target() {
+    let target = Vec2::new(-380., 0.);
+    let mut harness = Harness::new();
+    harness.mouse(target).behaviour(Behaviour::Seek);
+    let ship = harness.spawn_ship(Vec2::new(380., 0.), Vec2::ZERO);
+
+    let mut closest = f32::INFINITY;
+    harness.run(1., |harness| {
+        let position = harness.position(ship);
+        // never turns back through the middle of the world
+        assert!(position.x.abs() > 300., "at {position}");
+        closest = closest.min(position.distance(target));
+    });
+    assert!(closest < 10., "closest {closest}");
+}
+
+#[derive(Resource, Default)]
+struct ArrivedEvents(Vec<Arrived>);
+
+fn record_arrived_system(mut events: EventReader<Arrived>, mut arrived: ResMut<ArrivedEvents>) {
+    arrived.0.extend(events.read().copied());
+}
+
+#[test]
+fn arrive_should_stop_without_overshoot() {
+    let target = Vec2::new(200., 0.);
+    let mut harness = Harness::new();
+    harness
+        .app
+        .init_resource::<ArrivedEvents>()
+        .add_systems(FixedPostUpdate, record_arrived_system);
+    harness.mouse(target).behaviour(Behaviour::Arrive);
+    // less than half the world away, so the short way round is to the right
+    let ship = harness.spawn_ship(Vec2::new(-150., 0.), Vec2::ZERO);
+
+    harness.run(6., |harness| {
+        let x = harness.position(ship).x;
+        assert!(x <= target.x + 1., "overshot to {x}");
+    });
+
+    assert!(harness.position(ship).distance(target) < 5.);
+    assert!(harness.velocity(ship).length() < 5.);
+    // once, however long it then sits there
+    let arrived = &harness.world().resource::<ArrivedEvents>().0;
+    assert_eq!(arrived, &[Arrived { ship, target }]);
+}
+
+#[test]
+fn arrive_should_settle_with_every_deceleration() {
+    for deceleration in Deceleration::value_variants() {
+        let target = Vec2::new(100., 50.);
+        let mut harness = Harness::new();
+        harness.world_mut().insert_resource(ArriveSettings {
+            deceleration: *deceleration,
+            ..default()
+        });
+        harness.mouse(target).behaviour(Behaviour::Arrive);
+        let ship = harness.spawn_ship(Vec2::new(-150., -50.), Vec2::new(0., 200.));
+
+        harness.run(6., |_| {});
+
+        assert!(
+            harness.world().get::<Settled>(ship).is_some(),
+            "{deceleration:?} at {}",
+            harness.position(ship)
+        );
+    }
+}
+
+#[test]
+fn inspected_ships_should_record_each_behaviours_steering() {
+    let mut harness = Harness::new();
+    harness.behaviour(Behaviour::Ecosystem);
+    let ship = harness.spawn_ship(Vec2::new(-100., 0.), Vec2::new(50., 0.));
+    harness
+        .world_mut()
+        .entity_mut(ship)
+        .insert(SteeringParts::default());
+
+    harness.update(5);
+
+    // joined as prey, so it flocks
+    let parts = &harness.world().get::<SteeringParts>(ship).unwrap().0;
+    let behaviours: Vec<&str> = parts.iter().map(|(behaviour, _)| *behaviour).collect();
+    assert_eq!(behaviours[..3], ["separate", "align", "cohere"]);
+}
+
+#[test]
+fn flee_should_keep_getting_further_away() {
+    let mut harness = Harness::new();
+    harness.mouse(Vec2::ZERO).behaviour(Behaviour::Flee);
+    let ship = harness.spawn_ship(Vec2::new(30., 10.), Vec2::ZERO);
+
+    let mut previous = harness.position(ship).length();
+    // not long enough to wrap round the edge of the world
+    harness.run(1.5, |harness| {
+        let distance = harness.position(ship).length();
+        assert!(distance >= previous, "{distance} < {previous}");
+        previous = distance;
+    });
+    assert!(previous > 150.);
+}
+
+#[derive(Resource, Default)]
+struct Catches(usize);
+
+// pursue moves the target somewhere random when it's caught
+fn count_catches_system(
+    mut collisions: EventReader<CollisionStarted>,
+    mut catches: ResMut<Catches>,
+) {
+    catches.0 += collisions.read().count();
+}
+
+#[test]
+fn pursue_should_catch_linear_target() {
+    let mut harness = Harness::new();
+    harness
+        .app
+        .init_resource::<Catches>()
+        .add_systems(FixedPostUpdate, count_catches_system);
+    harness.spawn_ship(Vec2::new(0., -200.), Vec2::ZERO);
+    harness.behaviour(Behaviour::Pursue).update(1);
+
+    let target = harness.single::<With<PursueTarget>>();
+    harness.world_mut().get_mut::<Position>(target).unwrap().0 = Vec2::new(-300., 150.);
+    harness
+        .world_mut()
+        .get_mut::<LinearVelocity>(target)
+        .unwrap()
+        .0 = Vec2::new(120., 0.);
+
+    harness.run(4., |_| {});
+    assert!(harness.world().resource::<Catches>().0 > 0);
+}
+
+// closest distance from `point` to the looping path
+fn distance_to_path(point: Vec2, path: &[Vec2]) -> f32 {
+    (0..path.len())
+        .map(|i| {
+            point.distance(project_onto_segment(
+                point,
+                path[i],
+                path[(i + 1) % path.len()],
+            ))
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[test]
+fn path_follower_should_stay_within_radius() {
+    let mut harness = Harness::new();
+    harness.behaviour(Behaviour::PathFollow);
+    let ship = harness.spawn_ship(Vec2::new(0., -150.), Vec2::new(100., 0.));
+    let path = harness.world().resource::<Path>();
+    let (points, radius) = (path.points.clone(), path.radius);
+
+    let mut inside = 0;
+    let mut ticks = 0;
+    // several laps
+    harness.run(20., |harness| {
+        let distance = distance_to_path(harness.position(ship), &points);
+        // it only turns back once it's predicted to leave the path, so swings
+        // wide at the corners
+        assert!(distance < radius * 2.5, "{distance} from path");
+        if distance <= radius {
+            inside += 1;
+        }
+        ticks += 1;
+    });
+
+    assert!(
+        inside * 3 > ticks * 2,
+        "only inside {inside} of {ticks} ticks"
+    );
+}
+
+#[test]
+fn queue_ships_should_stay_out_of_walls() {
+    let mut harness = Harness::new();
+    harness.seed(5).behaviour(Behaviour::Queue);
+    let mut walls = harness.world_mut().query::<&Obstacle>();
+    let walls: Vec<Rect> = walls
+        .iter(harness.world())
+        .map(|wall| Rect::from_corners(wall.0[0], wall.0[2]))
+        .collect();
+    assert_eq!(walls.len(), 2);
+
+    let mut furthest = f32::NEG_INFINITY;
+    harness.run(20., |harness| {
+        let mut ships = harness
+            .world_mut()
+            .query_filtered::<&Position, With<Ship>>();
+        for position in ships.iter(harness.world()) {
+            assert!(
+                !walls.iter().any(|wall| wall.contains(position.0)),
+                "{position:?}"
+            );
+            furthest = furthest.max(position.0.x);
+        }
+    });
+    // and still get through the gap
+    assert!(furthest > 100., "{furthest}");
+}
+
+#[test]
+fn leaving_pursue_should_remove_target() {
+    let mut harness = Harness::new();
+    let ship = harness.spawn_ship(Vec2::ZERO, Vec2::ZERO);
+
+    harness.behaviour(Behaviour::Pursue);
+    assert_eq!(harness.count::<With<PursueTarget>>(), 1);
+    assert!(harness.world().get::<MaxLinearSpeed>(ship).unwrap().0 > SHIP_MAX_SPEED);
+
+    harness.behaviour(Behaviour::Seek);
+    assert_eq!(harness.count::<With<PursueTarget>>(), 0);
+    assert_eq!(
+        harness.world().get::<MaxLinearSpeed>(ship).unwrap().0,
+        SHIP_MAX_SPEED
+    );
+}
+
+#[test]
+fn every_behaviour_should_clean_up_after_itself() {
+    let mut harness = Harness::new();
+    harness.spawn_ship(Vec2::ZERO, Vec2::ZERO);
+    harness.update(1);
+    let behaviours: Vec<Behaviour> = harness
+        .world()
+        .resource::<BehaviourRegistry>()
+        .0
+        .iter()
+        .map(|entry| entry.behaviour.clone())
+        .collect();
+
+    for behaviour in behaviours {
+        let entities = harness.count::<()>();
+
+        harness.behaviour(behaviour.clone()).update(30);
+        harness.behaviour(Behaviour::Seek);
+        // despawns happen through commands
+        harness.update(1);
+
+        assert_eq!(harness.count::<()>(), entities, "{behaviour:?}");
+        assert_eq!(harness.count::<With<Ship>>(), 1, "{behaviour:?}");
+    }
+}
+
+#[derive(Resource, Default)]
+struct Ticks {
+    all: usize,
+    due: usize,
+    moved: usize,
+    last: Vec2,
+}
+
+fn count_due_system(ship: Single<(&LodState, &Position)>, mut ticks: ResMut<Ticks>) {
+    let (lod, position) = *ship;
+    ticks.all += 1;
+    ticks.due += lod.due as usize;
+    ticks.moved += (position.0 != ticks.last) as usize;
+    ticks.last = position.0;
+}
+
+#[test]
+fn distant_ships_should_steer_less_often_but_still_move() {
+    let target = Vec2::new(200., 0.);
+    let mut harness = Harness::new();
+    harness
+        .app
+        .init_resource::<Ticks>()
+        .insert_resource(SteeringLod {
+            enabled: true,
+            // outside the world, so the ship stays distant however it wraps
+            focus: LodFocus::Point(Vec2::new(-2000., 0.)),
+            tiers: vec![LodTier {
+                distance: 1000.,
+                interval: 4,
+            }],
+            off_screen_interval: 4,
+        })
+        .add_systems(FixedPostUpdate, count_due_system);
+    harness.mouse(target).behaviour(Behaviour::Seek);
+    let ship = harness.spawn_ship(Vec2::new(-200., -50.), Vec2::ZERO);
+
+    let mut closest = f32::INFINITY;
+    harness.run(4., |harness| {
+        closest = closest.min(harness.position(ship).distance(target));
+    });
+
+    let ticks = harness.world().resource::<Ticks>();
+    assert_eq!(
+        ticks.due,
+        ticks.all.div_ceil(4),
+        "{} of {}",
+        ticks.due,
+        ticks.all
+    );
+    // every tick after its first steering update
+    assert!(
+        ticks.moved >= ticks.all - 4,
+        "{} of {}",
+        ticks.moved,
+        ticks.all
+    );
+    // holding its steering between updates, it still gets there
+    assert!(closest < 20., "closest {closest}");
+}
@@ -0,0 +1,113 @@
+// shared by the integration tests. Not every test file uses all of it
+#![allow(dead_code)]
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_rand::prelude::*;
+use boids::game_plugin::{ShipAssets, ship_bundle};
+use boids::headless_plugin::HeadlessPlugin;
+use boids::input_plugin::{InputPlugin, MousePos};
+use boids::steering_plugin::{Behaviour, SteeringPlugin};
+
+/// Seconds of simulation each update advances, set by `HeadlessPlugin`
+pub const UPDATE: f32 = 1. / 60.;
+
+/// An `App` with the steering and physics plugins but no window, advanced
+/// by hand
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((HeadlessPlugin, InputPlugin, SteeringPlugin));
+        app.finish();
+        app.cleanup();
+
+        Self { app }
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    /// Switches behaviour, running its `OnEnter` and the previous one's `OnExit`
+    pub fn behaviour(&mut self, behaviour: Behaviour) -> &mut Self {
+        self.world_mut()
+            .resource_mut::<NextState<Behaviour>>()
+            .set(behaviour);
+        self.update(1);
+        self
+    }
+
+    /// Seeds the global rng, for runs which should repeat exactly
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        let world = self.world_mut();
+        let global = world
+            .query_filtered::<Entity, With<Global>>()
+            .single(world)
+            .unwrap();
+        world
+            .entity_mut(global)
+            .insert(RngSeed::<WyRand>::from_seed(seed.to_le_bytes()));
+        self
+    }
+
+    pub fn mouse(&mut self, position: Vec2) -> &mut Self {
+        self.world_mut().resource_mut::<MousePos>().0 = position;
+        self
+    }
+
+    pub fn spawn_ship(&mut self, position: Vec2, velocity: Vec2) -> Entity {
+        let assets = ShipAssets::placeholder();
+
+        self.world_mut()
+            // position too, so it can be read before the first update
+            .spawn((
+                ship_bundle(&assets, position),
+                Position(position),
+                LinearVelocity(velocity),
+            ))
+            .id()
+    }
+
+    /// Each update is `UPDATE` seconds, about one fixed tick
+    pub fn update(&mut self, updates: u32) -> &mut Self {
+        for _ in 0..updates {
+            self.app.update();
+        }
+        self
+    }
+
+    /// Updates for `seconds`, calling `each` after every one
+    pub fn run(&mut self, seconds: f32, mut each: impl FnMut(&mut Self)) -> &mut Self {
+        for _ in 0..(seconds / UPDATE).round() as u32 {
+            self.app.update();
+            each(self);
+        }
+        self
+    }
+
+    pub fn position(&self, entity: Entity) -> Vec2 {
+        self.world().get::<Position>(entity).unwrap().0
+    }
+
+    pub fn velocity(&self, entity: Entity) -> Vec2 {
+        self.world().get::<LinearVelocity>(entity).unwrap().0
+    }
+
+    pub fn single<F: bevy::ecs::query::QueryFilter>(&mut self) -> Entity {
+        let mut query = self.world_mut().query_filtered::<Entity, F>();
+        query.single(self.world()).unwrap()
+    }
+
+    pub fn count<F: bevy::ecs::query::QueryFilter>(&mut self) -> usize {
+        let mut query = self.world_mut().query_filtered::<Entity, F>();
+        query.iter(self.world()).count()
+    }
+}
@@ -0,0 +1,107 @@
+// parallel steering against the same run done sequentially. Bevy's compute task
+// pool is set up once per process, so the sequential run is this test again in
+// a child process with a single thread, where `par_iter_mut` runs as a plain loop
+mod common;
+
+use std::env;
+use std::process::Command;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::tasks::{ComputeTaskPool, TaskPoolBuilder};
+use boids::steering_plugin::{Behaviour, Ship};
+use common::Harness;
+
+/// Set in the child process to its number of compute threads
+const THREADS: &str = "BOIDS_TEST_THREADS";
+
+// queue and ecosystem use random numbers alongside the parallel systems, and
+// ecosystem flocks with `par_map`
+const BEHAVIOURS: [Behaviour; 3] = [Behaviour::Queue, Behaviour::Ecosystem, Behaviour::Wander];
+
+// every ship's position after a few seconds in `behaviour`
+fn positions_after(behaviour: Behaviour) -> Vec<Vec2> {
+    let mut harness = Harness::new();
+    harness.seed(3);
+    for i in 0..40 {
+        let i = i as f32;
+        harness.spawn_ship(Vec2::from_angle(i) * i * 5., Vec2::from_angle(i * 2.) * 80.);
+    }
+    harness.behaviour(behaviour).update(180);
+
+    let mut query = harness
+        .world_mut()
+        .query_filtered::<(Entity, &Position), With<Ship>>();
+    let mut ships: Vec<(Entity, Vec2)> = query
+        .iter(harness.world())
+        .map(|(entity, position)| (entity, position.0))
+        .collect();
+    ships.sort_by_key(|(entity, _)| *entity);
+    ships.into_iter().map(|(_, position)| position).collect()
+}
+
+// exactly, as bits, so the parent can compare without rounding
+fn print_positions(behaviour: &Behaviour, positions: &[Vec2]) {
+    for position in positions {
+        println!(
+            "position {behaviour:?} {} {}",
+            position.x.to_bits(),
+            position.y.to_bits()
+        );
+    }
+}
+
+fn parse_positions(output: &str, behaviour: &Behaviour) -> Vec<Vec2> {
+    let prefix = format!("position {behaviour:?} ");
+
+    output
+        .lines()
+        // the first may follow libtest's name for the test on the same line
+        .filter_map(|line| line.split_once(&prefix))
+        .map(|(_, bits)| {
+            let (x, y) = bits.split_once(' ').unwrap();
+            Vec2::new(
+                f32::from_bits(x.parse().unwrap()),
+                f32::from_bits(y.parse().unwrap()),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn parallel_steering_should_match_sequential() {
+    if let Ok(threads) = env::var(THREADS) {
+        let threads = threads.parse().unwrap();
+        ComputeTaskPool::get_or_init(|| TaskPoolBuilder::new().num_threads(threads).build());
+        for behaviour in BEHAVIOURS {
+            print_positions(&behaviour, &positions_after(behaviour.clone()));
+        }
+        return;
+    }
+
+    // several threads even on a single core machine
+    let pool = ComputeTaskPool::get_or_init(|| TaskPoolBuilder::new().num_threads(4).build());
+    assert!(pool.thread_num() > 1);
+
+    let sequential = Command::new(env::current_exe().unwrap())
+        .args([
+            "parallel_steering_should_match_sequential",
+            "--exact",
+            "--nocapture",
+        ])
+        .env(THREADS, "1")
+        .output()
+        .unwrap();
+    assert!(sequential.status.success(), "{sequential:?}");
+    let sequential = String::from_utf8(sequential.stdout).unwrap();
+
+    for behaviour in BEHAVIOURS {
+        let expected = parse_positions(&sequential, &behaviour);
+        assert!(!expected.is_empty(), "{behaviour:?}");
+        assert_eq!(
+            positions_after(behaviour.clone()),
+            expected,
+            "{behaviour:?}"
+        );
+    }
+}